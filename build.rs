@@ -1,7 +1,9 @@
 
 fn main(){
     tonic_build::configure()
-        .build_server(false)
+        // the server stubs are only used by the mock account service in the tests
+        .build_server(true)
+        .server_mod_attribute("account", "#[cfg(test)]")
         .compile_protos(
             &["grpc-protobufs/account/account_service.proto"],
             &["grpc-protobufs/account"]
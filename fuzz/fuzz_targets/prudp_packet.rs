@@ -0,0 +1,16 @@
+#![no_main]
+
+use std::io::Cursor;
+use libfuzzer_sys::fuzz_target;
+use rust_nex::prudp::packet::PRUDPV1Packet;
+
+fuzz_target!(|data: &[u8]| {
+    let mut cursor = Cursor::new(data);
+
+    // a datagram can contain multiple packets, read them the same way the router does
+    while (cursor.position() as usize) < data.len(){
+        if PRUDPV1Packet::new(&mut cursor).is_err(){
+            break;
+        }
+    }
+});
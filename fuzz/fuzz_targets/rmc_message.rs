@@ -0,0 +1,11 @@
+#![no_main]
+
+use std::io::Cursor;
+use libfuzzer_sys::fuzz_target;
+use rust_nex::rmc::message::RMCMessage;
+use rust_nex::rmc::response::RMCResponse;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = RMCMessage::new(&mut Cursor::new(data));
+    let _ = RMCResponse::new(&mut Cursor::new(data));
+});
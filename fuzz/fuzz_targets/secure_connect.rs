@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_nex::kerberos::TicketInternalData;
+use rust_nex::prudp::secure::read_secure_connection_data;
+use rust_nex::rmc::structures::RmcSerialize;
+
+const SERVER_KEY: [u8; 16] = [0x42; 16];
+
+fuzz_target!(|data: &[u8]| {
+    // the CONNECT payload exactly like the client sent it, this mostly ends at the signature check
+    let _ = read_secure_connection_data(data, &[SERVER_KEY]);
+
+    // a valid ticket with whatever request data the client makes up
+    let mut payload = Vec::new();
+
+    TicketInternalData::new(2).encrypt(SERVER_KEY, None).to_vec().serialize(&mut payload).unwrap();
+    data.to_vec().serialize(&mut payload).unwrap();
+
+    let _ = read_secure_connection_data(&payload, &[SERVER_KEY]);
+});
@@ -0,0 +1,61 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_nex::nex::account::Account;
+use rust_nex::prudp::sockaddr::PRUDPSockAddr;
+use rust_nex::prudp::station_url::StationUrl;
+use rust_nex::reggie::EdgeNodeInfo;
+use rust_nex::rmc::protocols::notifications::NotificationEvent;
+use rust_nex::rmc::structures::any::Any;
+use rust_nex::rmc::structures::authentication_info::AuthenticationInfo;
+use rust_nex::rmc::structures::connection_data::ConnectionData;
+use rust_nex::rmc::structures::matchmake::{
+    AutoMatchmakeParam, CreateMatchmakeSessionParam, Gathering, JoinMatchmakeSessionParam,
+    MatchmakeBlockListParam, MatchmakeParam, MatchmakeSession, MatchmakeSessionSearchCriteria,
+};
+use rust_nex::rmc::structures::qbuffer::QBuffer;
+use rust_nex::rmc::structures::result_range::ResultRange;
+use rust_nex::rmc::structures::variant::Variant;
+use rust_nex::rmc::structures::RmcSerialize;
+use rust_nex::rnex_proxy_common::ConnectionInitData;
+use rust_nex::versions::{with_nex_version_sync, NexVersion};
+
+fn deserialize<T: RmcSerialize>(mut data: &[u8]){
+    let _ = T::deserialize(&mut data);
+}
+
+fuzz_target!(|data: &[u8]| {
+    // the first two bytes pick the structure and whether structure headers are used
+    let [kind, flags, data @ ..] = data else {
+        return;
+    };
+
+    let version = if flags & 1 == 0 { NexVersion::new(3, 0, 0) } else { NexVersion::new(4, 0, 0) };
+
+    with_nex_version_sync(version, || match kind % 20 {
+        0 => deserialize::<String>(data),
+        1 => deserialize::<Vec<String>>(data),
+        2 => deserialize::<QBuffer>(data),
+        3 => deserialize::<Any>(data),
+        4 => deserialize::<Variant>(data),
+        5 => deserialize::<StationUrl>(data),
+        6 => deserialize::<AuthenticationInfo>(data),
+        7 => deserialize::<ConnectionData>(data),
+        8 => deserialize::<Gathering>(data),
+        9 => deserialize::<MatchmakeParam>(data),
+        10 => deserialize::<MatchmakeSession>(data),
+        11 => deserialize::<MatchmakeSessionSearchCriteria>(data),
+        12 => deserialize::<AutoMatchmakeParam>(data),
+        13 => deserialize::<CreateMatchmakeSessionParam>(data),
+        14 => deserialize::<MatchmakeBlockListParam>(data),
+        15 => deserialize::<JoinMatchmakeSessionParam>(data),
+        16 => deserialize::<ResultRange>(data),
+        17 => deserialize::<NotificationEvent>(data),
+        18 => deserialize::<ConnectionInitData>(data),
+        _ => {
+            deserialize::<PRUDPSockAddr>(data);
+            deserialize::<EdgeNodeInfo>(data);
+            deserialize::<Account>(data);
+        }
+    });
+});
@@ -45,6 +45,52 @@ impl Parse for ProtoInputParams {
     }
 }
 
+/// Gets the condition under which a field with `#[min_version(major, minor[, patch])]` exists.
+fn min_version_condition(f: &syn::Field) -> Option<proc_macro2::TokenStream> {
+    let attr = f.attrs.iter().find(|a| a.path().is_ident("min_version"))?;
+
+    let version = attr
+        .parse_args_with(Punctuated::<LitInt, Token![,]>::parse_terminated)
+        .expect("min_version expects `major, minor` or `major, minor, patch`");
+
+    let parts: Vec<u32> = version
+        .iter()
+        .map(|v| v.base10_parse().expect("version parts have to be numbers"))
+        .collect();
+
+    let (major, minor, patch) = match parts[..] {
+        [major, minor] => (major, minor, 0),
+        [major, minor, patch] => (major, minor, patch),
+        _ => panic!("min_version expects `major, minor` or `major, minor, patch`"),
+    };
+
+    Some(quote! {
+        rust_nex::versions::current_nex_version() >= rust_nex::versions::NexVersion::new(#major, #minor, #patch)
+    })
+}
+
+fn is_extends(f: &syn::Field) -> bool {
+    f.attrs.iter().any(|a| {
+        a.path().segments.len() == 1
+            && a.path()
+                .segments
+                .first()
+                .is_some_and(|p| p.ident.to_string() == "extends")
+    })
+}
+
+/// Gets how a field is accessed on `self` and the name of the local it gets read into, tuple
+/// struct fields get named `field_<index>`.
+fn field_names(i: usize, f: &syn::Field) -> (syn::Member, Ident) {
+    match &f.ident {
+        Some(ident) => (syn::Member::Named(ident.clone()), ident.clone()),
+        None => (
+            syn::Member::Unnamed(syn::Index::from(i)),
+            Ident::new(&format!("field_{}", i), Span::call_site()),
+        ),
+    }
+}
+
 fn gen_serialize_data_struct(
     s: DataStruct,
     struct_attr: Option<&Attribute>,
@@ -52,21 +98,23 @@ fn gen_serialize_data_struct(
     let serialize_base_content = {
         let mut serialize_content = quote! {};
 
-        for f in &s.fields {
-            if f.attrs.iter().any(|a| {
-                a.path().segments.len() == 1
-                    && a.path()
-                        .segments
-                        .first()
-                        .is_some_and(|p| p.ident.to_string() == "extends")
-            }) {
+        for (i, f) in s.fields.iter().enumerate() {
+            if is_extends(f) {
                 continue;
             }
-            let ident = f.ident.as_ref().unwrap();
+            let (member, _) = field_names(i, f);
 
-            serialize_content.append_all(quote! {
-                self.#ident.serialize(writer)?;
-            })
+            if let Some(condition) = min_version_condition(f) {
+                serialize_content.append_all(quote! {
+                    if #condition {
+                        self.#member.serialize(writer)?;
+                    }
+                })
+            } else {
+                serialize_content.append_all(quote! {
+                    self.#member.serialize(writer)?;
+                })
+            }
         }
 
         quote! {
@@ -77,40 +125,49 @@ fn gen_serialize_data_struct(
     };
 
     let struct_ctor = {
-        let mut structure_content = quote! {};
-        for f in &s.fields {
-            let ident = f.ident.as_ref().unwrap();
-
-            structure_content.append_all(quote! {#ident, });
-        }
-
-        quote! {
-            Ok(Self{
-                #structure_content
-            })
+        let vars = s.fields.iter().enumerate().map(|(i, f)| field_names(i, f).1);
+
+        match &s.fields {
+            Fields::Named(_) => quote! {
+                Ok(Self{
+                    #(#vars,)*
+                })
+            },
+            Fields::Unnamed(_) => quote! {
+                Ok(Self(
+                    #(#vars,)*
+                ))
+            },
+            Fields::Unit => quote! {
+                Ok(Self)
+            },
         }
     };
 
     let deserialize_base_content = {
         let mut deserialize_content = quote! {};
 
-        for f in &s.fields {
-            if f.attrs.iter().any(|a| {
-                a.path().segments.len() == 1
-                    && a.path()
-                        .segments
-                        .first()
-                        .is_some_and(|p| p.ident.to_string() == "extends")
-            }) {
+        for (i, f) in s.fields.iter().enumerate() {
+            if is_extends(f) {
                 continue;
             }
 
-            let ident = f.ident.as_ref().unwrap();
+            let (_, var) = field_names(i, f);
             let ty = &f.ty;
 
-            deserialize_content.append_all(quote! {
-                let #ident = <#ty> :: deserialize(reader)?;
-            })
+            if let Some(condition) = min_version_condition(f) {
+                deserialize_content.append_all(quote! {
+                    let #var = if #condition {
+                        <#ty> :: deserialize(reader)?
+                    } else {
+                        ::core::default::Default::default()
+                    };
+                })
+            } else {
+                deserialize_content.append_all(quote! {
+                    let #var = <#ty> :: deserialize(reader)?;
+                })
+            }
         }
 
         quote! {
@@ -121,21 +178,15 @@ fn gen_serialize_data_struct(
 
     // generate base with extends stuff
 
+    let extends_field = s.fields.iter().enumerate().find(|(_, f)| is_extends(f));
+
     let serialize_base_content = if let Some(attr) = struct_attr {
         let version: Literal = attr.parse_args().expect("has to be a literal");
 
-        let pre_inner = if let Some(f) = s.fields.iter().find(|f| {
-            f.attrs.iter().any(|a| {
-                a.path().segments.len() == 1
-                    && a.path()
-                        .segments
-                        .first()
-                        .is_some_and(|p| p.ident.to_string() == "extends")
-            })
-        }) {
-            let ident = f.ident.as_ref().unwrap();
+        let pre_inner = if let Some((i, f)) = extends_field {
+            let (member, _) = field_names(i, f);
             quote! {
-                self.#ident.serialize(writer)?;
+                self.#member.serialize(writer)?;
             }
         } else {
             quote! {}
@@ -156,19 +207,11 @@ fn gen_serialize_data_struct(
     let deserialize_base_content = if let Some(attr) = struct_attr {
         let version: Literal = attr.parse_args().expect("has to be a literal");
 
-        let pre_inner = if let Some(f) = s.fields.iter().find(|f| {
-            f.attrs.iter().any(|a| {
-                a.path().segments.len() == 1
-                    && a.path()
-                        .segments
-                        .first()
-                        .is_some_and(|p| p.ident.to_string() == "extends")
-            })
-        }) {
-            let ident = f.ident.as_ref().unwrap();
+        let pre_inner = if let Some((i, f)) = extends_field {
+            let (_, var) = field_names(i, f);
             let ty = &f.ty;
             quote! {
-                let #ident = <#ty> :: deserialize(reader)?;
+                let #var = <#ty> :: deserialize(reader)?;
             }
         } else {
             quote! {}
@@ -187,7 +230,14 @@ fn gen_serialize_data_struct(
     (serialize_base_content, deserialize_base_content)
 }
 
-#[proc_macro_derive(RmcSerialize, attributes(extends, rmc_struct))]
+/// Derives `RmcSerialize` for structs (including tuple and generic structs, type parameters
+/// have to be `RmcSerialize` themselves) and enums.
+///
+/// Fields which only exist in newer nex versions can be marked with
+/// `#[min_version(major, minor[, patch])]`, they only get read and written if the current nex
+/// version (see `rust_nex::versions::current_nex_version`) is at least that and are set to their
+/// default value otherwise.
+#[proc_macro_derive(RmcSerialize, attributes(extends, rmc_struct, min_version))]
 pub fn rmc_serialize(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
 
@@ -363,8 +413,17 @@ pub fn rmc_serialize(input: TokenStream) -> TokenStream {
 
     let ident = derive_input.ident;
 
+    // every type parameter has to be serializable itself
+    let mut generics = derive_input.generics;
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(syn::parse_quote!(rust_nex::rmc::structures::RmcSerialize));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
     let tokens = quote! {
-        impl rust_nex::rmc::structures::RmcSerialize for #ident{
+        impl #impl_generics rust_nex::rmc::structures::RmcSerialize for #ident #ty_generics #where_clause{
             fn serialize(&self, writer: &mut dyn ::std::io::Write) -> rust_nex::rmc::structures::Result<()>{
                 #serialize_base_content
 
@@ -389,6 +448,8 @@ pub fn rmc_serialize(input: TokenStream) -> TokenStream {
 /// You will also need to assign each function inside the trait a method id by using the
 /// [`macro@method_id`] attribute.
 ///
+/// Protocol ids of 127 and above are sent using the extended (u16) protocol id encoding.
+///
 /// You can also specify to have the protocol to be non-returning by adding a second parameter to
 /// the attribute which is just `NoReturn` e.g. `#[rmc_proto(1, NoReturn)]`
 ///
@@ -413,6 +474,11 @@ pub fn rmc_proto(attr: TokenStream, input: TokenStream) -> TokenStream {
         properties,
     } = params;
 
+    // ids which dont fit into a single byte get sent using the extended protocol id encoding
+    if let Err(e) = proto_num.base10_parse::<u16>() {
+        return e.to_compile_error().into();
+    }
+
     let no_return_data =
         properties.is_some_and(|p| p.1.iter().any(|i| i.to_string() == "NoReturn"));
 
@@ -491,10 +557,49 @@ pub fn method_id(_attr: TokenStream, input: TokenStream) -> TokenStream {
     input
 }
 
+struct RmcStructParams {
+    proto: syn::Path,
+    fallback: Option<Ident>,
+}
+
+impl Parse for RmcStructParams {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let proto = input.parse()?;
+
+        let fallback = if input.parse::<Option<Token![,]>>()?.is_some() {
+            let key: Ident = input.parse()?;
+
+            if key != "fallback" {
+                return Err(syn::Error::new(key.span(), "expected `fallback = fn_name`"));
+            }
+
+            input.parse::<Token![=]>()?;
+
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self { proto, fallback })
+    }
+}
+
+/// Implements the `Local` trait of an rmc proto made with `define_rmc_proto!` and makes the struct
+/// callable over rmc.
+///
+/// Calls to protocols or methods which the struct doesnt implement get answered with
+/// `Core_NotImplemented`, to handle those yourself pass a fallback method with the same
+/// signature as `rmc_fallback`:
+/// ```ignore
+/// #[rmc_struct(Proto, fallback = unknown_call)]
+/// ```
 #[proc_macro_attribute]
 pub fn rmc_struct(attr: TokenStream, input: TokenStream) -> TokenStream {
     let type_data = parse_macro_input!(input as DeriveInput);
-    let mut ident = parse_macro_input!(attr as syn::Path);
+    let RmcStructParams {
+        proto: mut ident,
+        fallback,
+    } = parse_macro_input!(attr as RmcStructParams);
     let last_token = ident.segments.last_mut().expect("empty path?");
 
     last_token.ident = Ident::new(
@@ -504,11 +609,19 @@ pub fn rmc_struct(attr: TokenStream, input: TokenStream) -> TokenStream {
 
     let struct_name = &type_data.ident;
 
+    let fallback_impl = fallback.map(|fallback| {
+        quote! {
+            async fn rmc_fallback(&self, remote_response_connection: &rust_nex::util::SendingBufferConnection, protocol_id: u16, method_id: u32, call_id: u32, rest: Vec<u8>){
+                self.#fallback(remote_response_connection, protocol_id, method_id, call_id, rest).await
+            }
+        }
+    });
+
     let out = quote! {
         #type_data
 
         impl #ident for #struct_name{
-
+            #fallback_impl
         }
 
         impl rust_nex::rmc::protocols::RmcCallable for #struct_name{
@@ -52,6 +52,8 @@ impl RmcProtocolData{
                     ..
                 } = method;
 
+                let trace_name = LitStr::new(&format!("{}::{}", self.name, name), Span::call_site());
+
                 let raw_name = Ident::new(&format!("raw_{}", name), name.span());
                 quote!{
                     async fn #raw_name
@@ -98,7 +100,18 @@ impl RmcProtocolData{
 
                     }
 
+                    let param_names = parameters.iter().map(|(param_name, _)| param_name);
+
                     quote!{
+                        if rust_nex::rmc::trace::tracing_enabled_for(#id){
+                            #[allow(unused_imports)]
+                            use rust_nex::rmc::trace::{TraceDebug as _, TraceFallback as _};
+
+                            let params: &[String] = &[#((&rust_nex::rmc::trace::TraceValue(&#param_names)).trace_fmt()),*];
+
+                            log::debug!("{}({})", #trace_name, params.join(", "));
+                        }
+
                         let retval = self.#name
                     }.to_tokens(tokens);
 
@@ -114,6 +127,13 @@ impl RmcProtocolData{
 
                     if *has_returns{
                         quote!{
+                            if rust_nex::rmc::trace::tracing_enabled_for(#id){
+                                #[allow(unused_imports)]
+                                use rust_nex::rmc::trace::{TraceDebug as _, TraceFallback as _};
+
+                                log::debug!("{} -> {}", #trace_name, (&rust_nex::rmc::trace::TraceValue(&retval)).trace_fmt());
+                            }
+
                             let retval = retval?;
                             let mut vec = Vec::new();
                             rust_nex::rmc::structures::RmcSerialize::serialize(&retval, &mut vec).ok();
@@ -153,23 +173,12 @@ impl RmcProtocolData{
                             #id => self.#raw_name(data).await,
                         }.to_tokens(tokens);
                     }
+                    // unknown methods never make it here, `define_rmc_proto!` hands them to
+                    // `rmc_fallback` instead
                     quote!{
-                        v =>
+                        _ => return,
                     }.to_tokens(tokens);
 
-
-
-                    Brace::default().surround(tokens, |tokens|{
-                        quote!{
-                            log::error!("(protocol {})unimplemented method id called on protocol: {}", #id, v);
-                        }.to_tokens(tokens);
-                        if self.has_returns {
-                            quote! {
-                                Err(rust_nex::rmc::response::ErrorCode::Core_NotImplemented)
-                            }.to_tokens(tokens);
-                        }
-                    });
-
                 });
 
                 Semi::default().to_tokens(tokens);
@@ -266,16 +275,14 @@ impl RmcProtocolData{
                     }
 
                     quote!{
-                        let call_id = rand::random();
+                        let rmc_conn = <Self as rust_nex::rmc::protocols::HasRmcConnection>::get_connection(self);
 
                         let message = rust_nex::rmc::message::RMCMessage{
-                            call_id,
+                            call_id: rmc_conn.next_call_id(),
                             method_id: #method_id,
                             protocol_id: #proto_id,
                             rest_of_data: send_data
                         };
-
-                        let rmc_conn = <Self as rust_nex::rmc::protocols::HasRmcConnection>::get_connection(self);
                     }.to_tokens(tokens);
 
                     if *has_returns{
@@ -301,13 +308,22 @@ impl RmcProtocolData{
 
     fn generate_raw_info(&self, tokens: &mut TokenStream){
         let Self{
+            has_returns,
             name,
             id,
-            ..
+            methods,
         } = self;
 
         let raw_info_name = Ident::new(&format!("Raw{}Info", name), Span::call_site());
 
+        let method_ids = methods.iter().map(|m| &m.id);
+        let method_names = methods.iter().map(|m| {
+            let id = &m.id;
+            let name = LitStr::new(&m.name.to_string(), m.name.span());
+
+            quote!{ (#id, #name) }
+        });
+        let proto_name = LitStr::new(&name.to_string(), name.span());
 
         quote!{
             #[doc(hidden)]
@@ -315,6 +331,16 @@ impl RmcProtocolData{
 
             impl #raw_info_name {
                 pub const PROTOCOL_ID: u16 = #id;
+                pub const METHOD_IDS: &'static [u32] = &[#(#method_ids),*];
+                pub const NAME: &'static str = #proto_name;
+                pub const METHOD_NAMES: &'static [(u32, &'static str)] = &[#(#method_names),*];
+                pub const HAS_RETURNS: bool = #has_returns;
+                pub const INFO: rust_nex::rmc::protocols::ProtocolInfo = rust_nex::rmc::protocols::ProtocolInfo{
+                    id: Self::PROTOCOL_ID,
+                    name: Self::NAME,
+                    methods: Self::METHOD_NAMES,
+                    has_returns: Self::HAS_RETURNS,
+                };
             }
         }.to_tokens(tokens);
     }
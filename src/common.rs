@@ -34,4 +34,21 @@ pub fn setup(){
     }).unwrap();*/
 
     dotenv::dotenv().ok();
-}
\ No newline at end of file
+
+    // the packed nex version of the game (e.g. 30502 for 3.5.2), this decides which fields
+    // structures have
+    if let Some(version) = std::env::var("NEX_VERSION").ok().and_then(|s| s.parse().ok()) {
+        crate::versions::set_default_nex_version(crate::versions::NexVersion::from_packed(version));
+    }
+
+    if env_flag("RMC_TRACE") {
+        crate::rmc::trace::set_tracing_enabled(true);
+    }
+}
+
+/// Reads a flag from the environment, `1`, `true` and `yes` turn it on and anything else
+/// (including it not being set) turns it off.
+pub fn env_flag(name: &str) -> bool{
+    std::env::var(name)
+        .is_ok_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+}
@@ -16,13 +16,16 @@ use std::{env, fs};
 use std::io::Cursor;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::Arc;
+use std::time::Duration;
 use macros::{method_id, rmc_proto, rmc_struct};
 use tokio::io::AsyncReadExt;
 use tokio::net::{TcpListener, TcpSocket, TcpStream};
 use tokio::task;
 use tokio_rustls::TlsAcceptor;
 use rust_nex::define_rmc_proto;
-use rust_nex::executables::common::{OWN_IP_PRIVATE, SECURE_EDGE_NODE_HOLDER, SECURE_SERVER_ACCOUNT, SERVER_PORT};
+use rust_nex::executables::common::{ACCOUNT_BACKEND, CONNECTION_DATA_CONFIG, GUEST_LOGIN_ENABLED, MODERATION, MODERATION_FILE, OWN_IP_PRIVATE, SERVICE_ACCOUNTS, SECURE_EDGE_NODE_HOLDER, SECURE_SERVER_KEYRING, SECURE_SERVER_KEYS_FILE, SERVER_PORT};
+use rust_nex::kerberos::keyring::ServerKeyring;
+use rust_nex::nex::moderation::Moderation;
 use rust_nex::nex::auth_handler::AuthHandler;
 use rust_nex::reggie::EdgeNodeHolderConnectOption::DontRegister;
 use rust_nex::rmc::protocols::{new_rmc_gateway_connection, OnlyRemote};
@@ -51,6 +54,23 @@ pub static SECURE_PROXY_PORT: Lazy<u16> = Lazy::new(|| {
 async fn main() {
     setup();
 
+    // this also precomputes the server keys so the first logins dont have to derive them
+    let server_keys = SECURE_SERVER_KEYRING.clone();
+
+    if let Some(path) = SECURE_SERVER_KEYS_FILE.clone() {
+        ServerKeyring::spawn_reload_task(server_keys.clone(), path, Duration::from_secs(30));
+    }
+
+    let accounts = ACCOUNT_BACKEND.clone();
+
+    let service_accounts = SERVICE_ACCOUNTS.clone();
+
+    let moderation = MODERATION.clone();
+
+    if let Some(path) = MODERATION_FILE.clone() {
+        Moderation::spawn_reload_task(moderation.clone(), path, Duration::from_secs(30));
+    }
+
     let conn = TcpStream::connect(&*SECURE_EDGE_NODE_HOLDER).await.unwrap();
 
     let conn: SplittableBufferConnection = conn.into();
@@ -82,13 +102,24 @@ async fn main() {
             }
         };
         let controller = conn.clone();
+        let server_keys = server_keys.clone();
+        let accounts = accounts.clone();
+        let moderation = moderation.clone();
+        let service_accounts = service_accounts.clone();
         task::spawn(async move {
             info!("connection to secure backend established");
             new_rmc_gateway_connection(stream.into(), |_| {
                 Arc::new(AuthHandler {
-                    destination_server_acct: &SECURE_SERVER_ACCOUNT,
+                    accounts,
+                    moderation,
+                    client_addr: Some(user_connection_data.prudpsock_addr),
+                    destination_server_keys: server_keys,
+                    service_accounts,
+                    allow_guest_login: *GUEST_LOGIN_ENABLED,
+                    connection_data_config: CONNECTION_DATA_CONFIG.clone(),
                     build_name: "branch:origin/project/wup-agmj build:3_8_15_2004_0",
-                    control_server: controller
+                    control_server: controller,
+                    nex_version: Default::default(),
                 })
             });
         });
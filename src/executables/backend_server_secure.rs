@@ -8,12 +8,14 @@ use log::{error, info};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::task;
 use rust_nex::common::setup;
-use rust_nex::executables::common::{OWN_IP_PRIVATE, SECURE_EDGE_NODE_HOLDER, SERVER_PORT};
+use rust_nex::executables::common::{GUEST_LOGIN_ENABLED, OWN_IP_PRIVATE, RMC_GATEWAY_CONFIG, SECURE_EDGE_NODE_HOLDER, SERVER_PORT};
+use rust_nex::nex::account::GUEST_PID;
 use rust_nex::nex::matchmake::MatchmakeManager;
 use rust_nex::nex::remote_console::RemoteConsole;
-use rust_nex::nex::user::User;
+use rust_nex::nex::user::{GuestRestrictions, User};
 use rust_nex::reggie::EdgeNodeHolderConnectOption::DontRegister;
-use rust_nex::rmc::protocols::{new_rmc_gateway_connection, OnlyRemote};
+use rust_nex::rmc::interceptor::CallerIdentity;
+use rust_nex::rmc::protocols::{new_rmc_gateway_connection_with_config, OnlyRemote, RmcGatewayConfig};
 use rust_nex::rnex_proxy_common::ConnectionInitData;
 use rust_nex::rmc::protocols::RemoteInstantiatable;
 use rust_nex::util::SplittableBufferConnection;
@@ -58,11 +60,28 @@ async fn main() {
         let mmm = mmm.clone();
         task::spawn(async move {
             info!("connection to secure backend established");
-            new_rmc_gateway_connection(stream.into(), |r| {
+            // without guest login pid 100 is just a regular account
+            let guest = *GUEST_LOGIN_ENABLED && user_connection_data.pid == GUEST_PID;
+
+            let mut config = RmcGatewayConfig{
+                caller: Some(CallerIdentity{
+                    pid: user_connection_data.pid,
+                    address: user_connection_data.prudpsock_addr,
+                }),
+                nex_version: user_connection_data.nex_version().or(RMC_GATEWAY_CONFIG.nex_version),
+                ..RMC_GATEWAY_CONFIG.clone()
+            };
+
+            if guest {
+                config.interceptors = config.interceptors.with(GuestRestrictions);
+            }
+
+            new_rmc_gateway_connection_with_config(stream.into(), config, |r| {
                 Arc::new_cyclic(|this| User{
                     this: this.clone(),
                     ip: user_connection_data.prudpsock_addr,
                     pid: user_connection_data.pid,
+                    guest,
                     remote: RemoteConsole::new(r),
                     matchmake_manager: mmm,
                     station_url: Default::default()
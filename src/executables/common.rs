@@ -1,11 +1,20 @@
 use std::env;
 use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::PathBuf;
+use std::sync::Arc;
 use macros::{method_id, rmc_proto, RmcSerialize};
 use once_cell::sync::Lazy;
 use tonic::transport::Server;
 use rust_nex::define_rmc_proto;
 use rust_nex::prudp::station_url::StationUrl;
-use crate::nex::account::Account;
+use crate::common::env_flag;
+use crate::kerberos::keyring::ServerKeyring;
+use crate::nex::account::{kerberos_password_from_str, Account};
+use crate::nex::account_provider::AccountBackend;
+use crate::nex::auth_handler::ConnectionDataConfig;
+use crate::nex::moderation::Moderation;
+use crate::nex::service_accounts::ServiceAccountRegistry;
+use crate::rmc::protocols::RmcGatewayConfig;
 use crate::rmc::response::ErrorCode;
 
 pub static OWN_IP_PRIVATE: Lazy<Ipv4Addr> = Lazy::new(|| {
@@ -40,6 +49,118 @@ pub static AUTH_SERVER_ACCOUNT: Lazy<Account> =
 pub static SECURE_SERVER_ACCOUNT: Lazy<Account> =
     Lazy::new(|| Account::new(2, "Quazal Rendez-Vous", &KERBEROS_SERVER_PASSWORD));
 
+/// Previous passwords of the secure server account which are still accepted for tickets, this is
+/// a comma seperated list.
+pub static SECURE_SERVER_PREVIOUS_PASSWORDS: Lazy<Vec<[u8; 16]>> = Lazy::new(|| {
+    env::var("AUTH_SERVER_PREVIOUS_PASSWORDS")
+        .ok()
+        .map(|s| {
+            s.split(',')
+                .filter(|p| !p.is_empty())
+                .map(|p| kerberos_password_from_str(p).expect("AUTH_SERVER_PREVIOUS_PASSWORDS contains an invalid password"))
+                .collect()
+        })
+        .unwrap_or_default()
+});
+
+/// Optional file containing the secure server passwords (one per line, current one first), when
+/// set the keys get reloaded whenever the file changes.
+pub static SECURE_SERVER_KEYS_FILE: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    env::var("AUTH_SERVER_KEYS_FILE")
+        .ok()
+        .map(PathBuf::from)
+});
+
+pub static SECURE_SERVER_KEYRING: Lazy<Arc<ServerKeyring>> = Lazy::new(|| {
+    Arc::new(ServerKeyring::new(&SECURE_SERVER_ACCOUNT, &SECURE_SERVER_PREVIOUS_PASSWORDS))
+});
+
+/// The account backend selected with `ACCOUNT_BACKEND`, see [`AccountBackend::from_env`].
+pub static ACCOUNT_BACKEND: Lazy<Arc<AccountBackend>> = Lazy::new(|| {
+    Arc::new(AccountBackend::from_env().expect("unable to set up account backend"))
+});
+
+/// Service accounts which tickets can be issued for, loaded from `SERVICE_ACCOUNTS_FILE`.
+pub static SERVICE_ACCOUNTS: Lazy<Arc<ServiceAccountRegistry>> = Lazy::new(|| {
+    let Ok(path) = env::var("SERVICE_ACCOUNTS_FILE") else {
+        return Default::default();
+    };
+
+    Arc::new(ServiceAccountRegistry::load(&PathBuf::from(path)).expect("unable to load service accounts"))
+});
+
+fn special_protocols_from_env(key: &str) -> Vec<u8> {
+    env::var(key)
+        .ok()
+        .map(|s| {
+            s.split(',')
+                .filter(|p| !p.is_empty())
+                .map(|p| p.trim().parse().expect("invalid special protocol id"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn special_station_url_from_env(key: &str) -> Option<StationUrl> {
+    env::var(key)
+        .ok()
+        .map(|s| StationUrl::try_from(s.as_str()).expect("invalid special station url"))
+}
+
+/// Default special protocols and special station url which the auth server hands out, edge nodes
+/// can override them with `EDGE_SPECIAL_PROTOCOLS` and `EDGE_SPECIAL_STATION_URL`.
+pub static CONNECTION_DATA_CONFIG: Lazy<Arc<ConnectionDataConfig>> = Lazy::new(|| {
+    Arc::new(ConnectionDataConfig {
+        special_protocols: special_protocols_from_env("SPECIAL_PROTOCOLS"),
+        special_station_url: special_station_url_from_env("SPECIAL_STATION_URL"),
+    })
+});
+
+/// The special protocols and special station url this edge node advertises.
+pub static EDGE_SPECIAL_PROTOCOLS: Lazy<Vec<u8>> =
+    Lazy::new(|| special_protocols_from_env("EDGE_SPECIAL_PROTOCOLS"));
+pub static EDGE_SPECIAL_STATION_URL: Lazy<Option<StationUrl>> =
+    Lazy::new(|| special_station_url_from_env("EDGE_SPECIAL_STATION_URL"));
+
+/// Whether clients may log in with the guest account, guests only get a restricted set of
+/// methods on the secure server.
+pub static GUEST_LOGIN_ENABLED: Lazy<bool> = Lazy::new(|| env_flag("GUEST_LOGIN"));
+
+/// Settings for the rmc gateways of client connections, RMC_MAX_CONCURRENT_CALLS limits how many
+/// calls of one client get handled at once and RMC_SERIALIZED_PROTOCOLS is a comma separated list
+/// of protocol ids whose calls get handled strictly in order.
+pub static RMC_GATEWAY_CONFIG: Lazy<RmcGatewayConfig> = Lazy::new(|| {
+    let mut config = RmcGatewayConfig::default();
+
+    if let Some(max) = env::var("RMC_MAX_CONCURRENT_CALLS").ok().and_then(|s| s.parse().ok()) {
+        // 0 would reject every call
+        config.max_concurrent_calls = usize::max(max, 1);
+    }
+
+    if let Ok(protocols) = env::var("RMC_SERIALIZED_PROTOCOLS") {
+        config.serialized_protocols = protocols.split(',')
+            .map(|p| p.trim().parse().expect("RMC_SERIALIZED_PROTOCOLS has to be a list of protocol ids"))
+            .collect();
+    }
+
+    if let Some(max) = env::var("RMC_MAX_MESSAGE_SIZE").ok().and_then(|s| s.parse().ok()) {
+        config.read_limits.max_size = max;
+    }
+
+    config
+});
+
+/// Optional moderation file (bans and maintenance), it gets reloaded whenever it changes.
+pub static MODERATION_FILE: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    env::var("MODERATION_FILE")
+        .ok()
+        .map(PathBuf::from)
+});
+
+pub static MODERATION: Lazy<Arc<Moderation>> = Lazy::new(|| {
+    Arc::new(Moderation::new(env_flag("MAINTENANCE_MODE")))
+});
+
 pub static SECURE_EDGE_NODE_HOLDER: Lazy<SocketAddrV4> = Lazy::new(||{
     env::var("SECURE_EDGE_NODE_HOLDER")
         .ok()
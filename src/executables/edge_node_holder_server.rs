@@ -7,7 +7,7 @@ use tokio::net::TcpListener;
 use tokio::sync::RwLock;
 use rust_nex::common::setup;
 use rust_nex::executables::common::{OWN_IP_PRIVATE, SERVER_PORT};
-use rust_nex::reggie::{EdgeNodeHolderConnectOption, EdgeNodeManagement, LocalEdgeNodeHolder};
+use rust_nex::reggie::{EdgeNodeHolderConnectOption, EdgeNodeInfo, EdgeNodeManagement, LocalEdgeNodeHolder};
 use rust_nex::rmc::protocols::new_rmc_gateway_connection;
 use rust_nex::rmc::response::ErrorCode;
 use rust_nex::util::SplittableBufferConnection;
@@ -16,13 +16,17 @@ use rust_nex::rmc::structures::RmcSerialize;
 #[rmc_struct(EdgeNodeHolder)]
 struct EdgeNode{
     data_holder: Arc<DataHolder>,
-    address: SocketAddrV4
+    info: EdgeNodeInfo
 }
 
 impl EdgeNodeManagement for EdgeNode{
     async fn get_url(&self, seed: u64) -> Result<SocketAddrV4, ErrorCode> {
         self.data_holder.get_url(seed).await
     }
+
+    async fn get_node(&self, seed: u64) -> Result<EdgeNodeInfo, ErrorCode> {
+        self.data_holder.get_node(seed).await
+    }
 }
 
 #[rmc_struct(EdgeNodeHolder)]
@@ -33,6 +37,10 @@ struct DataHolder{
 
 impl EdgeNodeManagement for DataHolder{
     async fn get_url(&self, seed: u64) -> Result<SocketAddrV4, ErrorCode> {
+        Ok(self.get_node(seed).await?.address)
+    }
+
+    async fn get_node(&self, seed: u64) -> Result<EdgeNodeInfo, ErrorCode> {
         let nodes = self.edge_nodes.read().await;
 
         let nodes: Vec<_> = nodes.iter().filter_map(|n| n.upgrade()).collect();
@@ -44,10 +52,22 @@ impl EdgeNodeManagement for DataHolder{
 
         let node = &nodes[seed as usize % nodes.len()];
 
-        Ok(node.address)
+        Ok(node.info.clone())
     }
 }
 
+async fn register_edge_node(holder: &Arc<DataHolder>, conn: SplittableBufferConnection, info: EdgeNodeInfo){
+    let edge_node = EdgeNode{
+        info,
+        data_holder: holder.clone()
+    };
+
+    let node = new_rmc_gateway_connection(conn, move |_| Arc::new(edge_node));
+
+    let mut nodes = holder.edge_nodes.write().await;
+    nodes.push(Arc::downgrade(&node));
+}
+
 #[tokio::main]
 async fn main() {
     setup();
@@ -75,15 +95,10 @@ async fn main() {
                 new_rmc_gateway_connection(conn, |_| holder);
             },
             EdgeNodeHolderConnectOption::Register(address) => {
-                let edge_node = EdgeNode{
-                    address,
-                    data_holder: holder.clone()
-                };
-
-                let node = new_rmc_gateway_connection(conn, move |_| Arc::new(edge_node));
-
-                let mut nodes = holder.edge_nodes.write().await;
-                nodes.push(Arc::downgrade(&node));
+                register_edge_node(&holder, conn, EdgeNodeInfo::new(address)).await;
+            }
+            EdgeNodeHolderConnectOption::RegisterWithInfo(info) => {
+                register_edge_node(&holder, conn, info).await;
             }
         }
 
@@ -80,10 +80,11 @@ async fn main() {
                 }
             };
 
-            if let Err(e) = stream.send_buffer(&ConnectionInitData{
-                prudpsock_addr: conn.socket_addr,
-                pid: conn.user_id
-            }.to_data()).await{
+            if let Err(e) = stream.send_buffer(&ConnectionInitData::new(
+                conn.socket_addr,
+                conn.user_id,
+                conn.nex_version
+            ).to_data()).await{
                 error!("error connecting to backend: {}", e);
                 return;
             };
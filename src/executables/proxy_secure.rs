@@ -11,12 +11,15 @@ use tokio::time::sleep;
 use tokio_rustls::client::TlsStream;
 use tokio_tungstenite::MaybeTlsStream;
 use rust_nex::common::setup;
-use rust_nex::executables::common::{AUTH_SERVER_ACCOUNT, FORWARD_DESTINATION, OWN_IP_PRIVATE, OWN_IP_PUBLIC, SECURE_EDGE_NODE_HOLDER, SECURE_SERVER_ACCOUNT, SERVER_PORT};
+use rust_nex::executables::common::{AUTH_SERVER_ACCOUNT, EDGE_SPECIAL_PROTOCOLS, EDGE_SPECIAL_STATION_URL, FORWARD_DESTINATION, OWN_IP_PRIVATE, OWN_IP_PUBLIC, SECURE_EDGE_NODE_HOLDER, SECURE_SERVER_ACCOUNT, MODERATION, MODERATION_FILE, SECURE_SERVER_KEYRING, SECURE_SERVER_KEYS_FILE, SERVER_PORT};
+use rust_nex::kerberos::keyring::ServerKeyring;
+use rust_nex::nex::moderation::Moderation;
 use rust_nex::prudp::packet::VirtualPort;
 use rust_nex::prudp::router::Router;
 use rust_nex::prudp::secure::Secure;
 use rust_nex::prudp::unsecure::Unsecure;
-use rust_nex::reggie::EdgeNodeHolderConnectOption::{DontRegister, Register};
+use rust_nex::reggie::EdgeNodeHolderConnectOption::{DontRegister, RegisterWithInfo};
+use rust_nex::reggie::EdgeNodeInfo;
 use rust_nex::rmc::response::ErrorCode;
 use rust_nex::rnex_proxy_common::ConnectionInitData;
 use rust_nex::reggie::{RemoteEdgeNodeHolder, UnitPacketWrite};
@@ -33,20 +36,33 @@ async fn main() {
 
     let conn: SplittableBufferConnection = conn.into();
 
-    conn.send(Register(SocketAddrV4::new(*OWN_IP_PUBLIC, *SERVER_PORT)).to_data()).await;
+    conn.send(RegisterWithInfo(EdgeNodeInfo {
+        address: SocketAddrV4::new(*OWN_IP_PUBLIC, *SERVER_PORT),
+        special_protocols: EDGE_SPECIAL_PROTOCOLS.clone(),
+        special_station_url: EDGE_SPECIAL_STATION_URL.as_ref().map(|u| u.to_string()).unwrap_or_default(),
+    }).to_data()).await;
 
     let conn = new_rmc_gateway_connection(conn, |r| Arc::new(OnlyRemote::<RemoteEdgeNodeHolder>::new(r)));
 
 
 
+    if let Some(path) = SECURE_SERVER_KEYS_FILE.clone() {
+        ServerKeyring::spawn_reload_task(SECURE_SERVER_KEYRING.clone(), path, Duration::from_secs(30));
+    }
+
+    if let Some(path) = MODERATION_FILE.clone() {
+        Moderation::spawn_reload_task(MODERATION.clone(), path, Duration::from_secs(30));
+    }
+
     let (router_secure, _) = Router::new(SocketAddrV4::new(*OWN_IP_PRIVATE, *SERVER_PORT))
         .await
         .expect("unable to start router");
 
     let mut socket_secure = router_secure
-        .add_socket(VirtualPort::new(1, 10), Secure(
+        .add_socket(VirtualPort::new(1, 10), Secure::new(
             "6f599f81",
-            SECURE_SERVER_ACCOUNT.clone()
+            SECURE_SERVER_KEYRING.clone(),
+            MODERATION.clone()
         ))
         .await
         .expect("unable to add socket");
@@ -69,10 +85,11 @@ async fn main() {
                 }
             };
 
-            if let Err(e) = stream.send_buffer(&ConnectionInitData{
-                prudpsock_addr: conn.socket_addr,
-                pid: conn.user_id
-            }.to_data()).await{
+            if let Err(e) = stream.send_buffer(&ConnectionInitData::new(
+                conn.socket_addr,
+                conn.user_id,
+                conn.nex_version
+            ).to_data()).await{
                 error!("error connecting to backend: {}", e);
                 return;
             };
@@ -1,151 +1,669 @@
 use std::{env, result};
 use std::array::TryFromSliceError;
-use std::str::FromStr;
-use json::{object, JsonValue};
+use std::time::Duration;
+use log::warn;
 use once_cell::sync::Lazy;
-use reqwest::{Body, Method, Url};
-use reqwest::header::HeaderValue;
+use reqwest::{StatusCode, Url};
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use crate::grpc::account::Error::SomethingHappened;
-static API_KEY: Lazy<String> = Lazy::new(||{
-    let key = env::var("ACCOUNT_GQL_API_KEY")
-        .expect("no graphql ip specified");
-
-    key
+use tokio::time::sleep;
+use tonic::codegen::InterceptedService;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request};
+use crate::grpc::account::Error::{GraphQl, NotFound, Unauthorized, UpstreamDown};
+use crate::grpc::protobufs::account::account_client::AccountClient;
+use crate::grpc::protobufs::account::{ExchangeTokenForUserDataRequest, GetNexPasswordRequest, GetUserDataRequest, GetUserDataResponse};
+use crate::grpc::ApiKeyInterceptor;
+use crate::nex::account_provider::BanStatus;
+static API_KEY: Lazy<Option<String>> = Lazy::new(||{
+    env::var("ACCOUNT_GQL_API_KEY").ok()
 });
 
-static CLIENT_URI: Lazy<String> = Lazy::new(||{
+static CLIENT_URI: Lazy<Option<Url>> = Lazy::new(||{
     env::var("ACCOUNT_GQL_URL")
         .ok()
         .and_then(|s| s.parse().ok())
-        .expect("no graphql ip specified")
 });
 
+/// Whether the GraphQL schema of the account server has the `banned: Boolean` and
+/// `banReason: String` fields on users, bans only get checked with the account server if this is
+/// set as querying fields which dont exist fails the whole request (and with that every login).
+static BAN_FIELDS: Lazy<bool> = Lazy::new(|| crate::common::env_flag("ACCOUNT_GQL_BAN_FIELDS"));
+
+static GRPC_API_KEY: Lazy<Option<String>> = Lazy::new(||{
+    env::var("ACCOUNT_GRPC_API_KEY").ok()
+});
+
+static GRPC_URI: Lazy<Option<String>> = Lazy::new(||{
+    env::var("ACCOUNT_GRPC_URL").ok()
+});
 
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+/// How often a request is tried before giving up when the account server is unreachable.
+const MAX_ATTEMPTS: u32 = 3;
+/// Delay before the first retry, this doubles with every further retry.
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
 
 #[derive(Error, Debug)]
 pub enum Error{
     #[error(transparent)]
     Creation(#[from] reqwest::Error),
+    #[error("invalid response from the account server: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
     #[error(transparent)]
-    Json(#[from] json::Error),
-    #[error(transparent)]
-    Status(#[from] tonic::Status),
+    Transport(#[from] tonic::transport::Error),
+    #[error("the account server returned an error: {0}")]
+    Status(tonic::Status),
     #[error("invalid password size: {0}")]
     PasswordConversion(#[from] TryFromSliceError),
-    #[error("something happened")]
-    SomethingHappened
+    #[error("the requested user doesn't exist")]
+    NotFound,
+    #[error("the account server rejected our api key")]
+    Unauthorized,
+    #[error("the account server is unavailable: {0}")]
+    UpstreamDown(String),
+    #[error("the account server returned an error: {0}")]
+    GraphQl(String),
+    #[error("the account server doesnt support {0}")]
+    Unsupported(&'static str),
+    #[error("the url or api key of the account server isnt set")]
+    NotConfigured,
+}
+
+impl From<tonic::Status> for Error{
+    fn from(value: tonic::Status) -> Self {
+        match value.code() {
+            Code::NotFound => NotFound,
+            Code::Unauthenticated | Code::PermissionDenied => Unauthorized,
+            Code::Unavailable | Code::DeadlineExceeded => UpstreamDown(value.message().to_owned()),
+            _ => Error::Status(value),
+        }
+    }
 }
 
 pub type Result<T> = result::Result<T, Error>;
 
-pub struct Client(reqwest::Client);
+#[derive(Serialize)]
+struct GraphQlRequest<V>{
+    query: &'static str,
+    variables: V,
+}
+
+#[derive(Deserialize)]
+struct GraphQlResponse<T>{
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlError{
+    message: String,
+    #[serde(default)]
+    extensions: GraphQlErrorExtensions,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct GraphQlErrorExtensions{
+    code: Option<String>,
+}
+
+impl From<GraphQlError> for Error{
+    fn from(value: GraphQlError) -> Self {
+        match value.extensions.code.as_deref() {
+            Some("NOT_FOUND") => NotFound,
+            Some("UNAUTHENTICATED" | "UNAUTHORIZED" | "FORBIDDEN") => Unauthorized,
+            Some("INTERNAL_SERVER_ERROR" | "SERVICE_UNAVAILABLE") => UpstreamDown(value.message),
+            _ => GraphQl(value.message),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PidVariables{
+    pid: u32,
+}
+
+#[derive(Serialize)]
+struct UsernameVariables<'a>{
+    username: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserByPid<T>{
+    user_by_pid: Option<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserByUsername<T>{
+    user_by_username: Option<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NexPasswordData{
+    nex_password: String,
+}
+
+#[derive(Deserialize)]
+struct PidData{
+    pid: u32,
+}
+
+#[derive(Deserialize)]
+struct UsernameData{
+    username: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BanData{
+    #[serde(default)]
+    banned: Option<bool>,
+    #[serde(default)]
+    ban_reason: Option<String>,
+}
+
+/// GraphQL account server client, this is meant to be kept around as the underlying http client
+/// pools its connections.
+pub struct Client{
+    client: reqwest::Client,
+    uri: Url,
+    api_key: HeaderValue,
+    ban_fields: bool,
+}
 
 impl Client{
-    pub async fn new() -> Result<Self> {
-        Ok(Self(reqwest::ClientBuilder::new().build()?))
+    pub fn new() -> Result<Self> {
+        let (Some(uri), Some(api_key)) = (CLIENT_URI.clone(), API_KEY.as_ref()) else {
+            return Err(Error::NotConfigured);
+        };
+
+        Ok(Self::with_config(uri, api_key)?.with_ban_fields(*BAN_FIELDS))
+    }
+
+    pub fn with_config(uri: Url, api_key: &str) -> Result<Self> {
+        let Ok(api_key) = HeaderValue::from_str(api_key) else {
+            return Err(Error::NotConfigured);
+        };
+
+        let client = reqwest::ClientBuilder::new()
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .build()?;
+
+        Ok(Self{
+            client,
+            uri,
+            api_key,
+            ban_fields: false,
+        })
     }
 
-    async fn do_request(&self, request_data: JsonValue) -> Result<JsonValue>{
-        let mut request = reqwest::Request::new(Method::POST, Url::from_str(CLIENT_URI.as_str()).unwrap());
+    /// Enables checking bans with the account server, see `ACCOUNT_GQL_BAN_FIELDS`.
+    pub fn with_ban_fields(mut self, enabled: bool) -> Self{
+        self.ban_fields = enabled;
+        self
+    }
+
+    async fn do_request<T: DeserializeOwned>(&self, body: String) -> Result<T>{
+        let response = self.client.post(self.uri.clone())
+            .header("X-API-Key", self.api_key.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send().await
+            .map_err(|e| UpstreamDown(e.to_string()))?;
+
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => return Err(Unauthorized),
+            status if status.is_server_error() => return Err(UpstreamDown(status.to_string())),
+            _ => {}
+        }
 
-        *(request.body_mut()) = Some(Body::from(request_data.to_string()));
-        request.headers_mut().insert("X-API-Key", HeaderValue::from_str(&API_KEY).unwrap());
-        request.headers_mut().insert("Content-Type", HeaderValue::from_str("application/json").unwrap());
+        let text = response.text().await
+            .map_err(|e| UpstreamDown(e.to_string()))?;
 
-        let response = self.0.execute(request).await?;
+        let response: GraphQlResponse<T> = serde_json::from_str(&text)?;
 
-        Ok(json::parse(&response.text().await?)?)
+        if let Some(error) = response.errors.into_iter().next() {
+            return Err(error.into());
+        }
+
+        response.data.ok_or(GraphQl("response contained neither data nor errors".to_owned()))
     }
 
-    pub async fn get_nex_password(&mut self , pid: u32) -> Result<[u8; 16]>{
-        let req = self.do_request(object!{
-            "query": r"query($pid: Int!){
+    /// Runs a query, retrying with backoff if the account server is unavailable.
+    async fn query<V: Serialize, T: DeserializeOwned>(&self, query: &'static str, variables: V) -> Result<T>{
+        let body = serde_json::to_string(&GraphQlRequest{ query, variables })?;
+
+        let mut attempt = 1;
+
+        loop {
+            match self.do_request(body.clone()).await {
+                Err(UpstreamDown(e)) if attempt < MAX_ATTEMPTS => {
+                    warn!("account server unavailable (attempt {}/{}): {}", attempt, MAX_ATTEMPTS, e);
+
+                    sleep(RETRY_BACKOFF * 2u32.pow(attempt - 1)).await;
+
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    pub async fn get_nex_password(&self, pid: u32) -> Result<[u8; 16]>{
+        let data: UserByPid<NexPasswordData> = self.query(r"query($pid: Int!){
                 userByPid(pid: $pid){
                     nexPassword
                 }
-            }",
-            "variables": {
-                "pid": pid
-            }
-        }).await?;
-
-        let Some(val) = req.entries()
-            .find(|v| v.0 == "data")
-            .ok_or(SomethingHappened)?.1
-            .entries()
-            .find(|v| v.0 == "userByPid")
-            .ok_or(SomethingHappened)?.1
-            .entries()
-            .find(|v| v.0 == "nexPassword")
-            .ok_or(SomethingHappened)?.1
-            .as_str() else {
-            return Err(SomethingHappened);
-        };
+            }", PidVariables{ pid }).await?;
 
-        Ok(val.as_bytes().try_into().map_err(|_| SomethingHappened)?)
+        let user = data.user_by_pid.ok_or(NotFound)?;
+
+        Ok(user.nex_password.as_bytes().try_into()?)
     }
 
-    /*pub async fn get_user_data(&mut self , pid: u32) -> Result<GetUserDataResponse>{
-        let req = Request::new(GetUserDataRequest{
-            pid
-        });
+    pub async fn get_pid_by_username(&self, username: &str) -> Result<u32>{
+        let data: UserByUsername<PidData> = self.query(r"query($username: String!){
+                userByUsername(username: $username){
+                    pid
+                }
+            }", UsernameVariables{ username }).await?;
 
-        let response = self.0.get_user_data(req).await?.into_inner();
+        Ok(data.user_by_username.ok_or(NotFound)?.pid)
+    }
 
-        Ok(response)
-    }*/
+    pub async fn get_username_by_pid(&self, pid: u32) -> Result<String>{
+        let data: UserByPid<UsernameData> = self.query(r"query($pid: Int!){
+                userByPid(pid: $pid){
+                    username
+                }
+            }", PidVariables{ pid }).await?;
+
+        Ok(data.user_by_pid.ok_or(NotFound)?.username)
+    }
+
+    /// Accounts count as not banned if the schema doesnt have the ban fields.
+    pub async fn get_ban_status(&self, pid: u32) -> Result<BanStatus>{
+        if !self.ban_fields {
+            return Ok(BanStatus::NotBanned);
+        }
+
+        let data: UserByPid<BanData> = self.query(r"query($pid: Int!){
+                userByPid(pid: $pid){
+                    banned
+                    banReason
+                }
+            }", PidVariables{ pid }).await?;
+
+        let user = data.user_by_pid.ok_or(NotFound)?;
+
+        if !user.banned.unwrap_or(false) {
+            return Ok(BanStatus::NotBanned);
+        }
+
+        Ok(BanStatus::Banned {
+            reason: user.ban_reason,
+            until: None,
+        })
+    }
 }
 
 
 
-/*
+/// Client for the account grpc service of pretendos account server, this is used instead of the
+/// GraphQL api when `ACCOUNT_BACKEND` is `grpc`.
+#[derive(Clone)]
+pub struct GrpcClient(AccountClient<InterceptedService<Channel, ApiKeyInterceptor>>);
 
-pub struct Client(AccountClient<InterceptedService<Channel, InterceptorFunc>>);
+impl GrpcClient{
+    pub fn new() -> Result<Self>{
+        let (Some(uri), Some(api_key)) = (GRPC_URI.as_ref(), GRPC_API_KEY.as_ref()) else {
+            return Err(Error::NotConfigured);
+        };
 
-impl Client{
-    pub async fn new() -> Result<Self>{
-        let channel = Channel::from_static(&*CLIENT_URI).connect().await?;
+        Self::with_config(uri, api_key)
+    }
 
-        let func = Box::new(&|mut req: Request<()>|{
-            req.metadata_mut().insert("x-api-key", API_KEY.clone());
-            Ok(req)
-        }) as InterceptorFunc;
+    /// Creates the client, the connection only gets established once the first request is made.
+    pub fn with_config(uri: &str, api_key: &str) -> Result<Self>{
+        let channel = Endpoint::from_shared(uri.to_owned())?
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .connect_lazy();
+
+        let Ok(interceptor) = ApiKeyInterceptor::new(api_key) else {
+            return Err(Error::NotConfigured);
+        };
 
-        let client = AccountClient::with_interceptor(channel, func);
-        Ok(Self(client))
+        Ok(Self(AccountClient::with_interceptor(channel, interceptor)))
     }
 
-    pub async fn get_nex_password(&mut self , pid: u32) -> Result<[u8; 16]>{
+    pub async fn get_nex_password(&self, pid: u32) -> Result<[u8; 16]>{
         let req = Request::new(GetNexPasswordRequest{
             pid
         });
 
-        let response = self.0.get_nex_password(req).await?.into_inner();
+        let response = self.0.clone().get_nex_password(req).await?.into_inner();
 
         Ok(response.password.as_bytes().try_into()?)
     }
 
-    pub async fn get_user_data(&mut self , pid: u32) -> Result<GetUserDataResponse>{
+    pub async fn get_user_data(&self, pid: u32) -> Result<GetUserDataResponse>{
         let req = Request::new(GetUserDataRequest{
             pid
         });
 
-        let response = self.0.get_user_data(req).await?.into_inner();
+        let response = self.0.clone().get_user_data(req).await?.into_inner();
 
         Ok(response)
     }
+
+    pub async fn get_username_by_pid(&self, pid: u32) -> Result<String>{
+        Ok(self.get_user_data(pid).await?.username)
+    }
+
+    /// Gets the pid of the account an nnid/pnid token was issued for.
+    pub async fn get_pid_by_token(&self, token: &str) -> Result<u32>{
+        let req = Request::new(ExchangeTokenForUserDataRequest{
+            token: token.to_owned()
+        });
+
+        Ok(self.0.clone().exchange_token_for_user_data(req).await?.into_inner().pid)
+    }
+
+    /// The grpc service has no way of looking up users by name, so logging in with a username
+    /// fails with `RendezVous_InvalidUsername` on this backend.
+    pub async fn get_pid_by_username(&self, _username: &str) -> Result<u32>{
+        Err(Error::Unsupported("looking up pids by username"))
+    }
+
+    pub async fn get_ban_status(&self, pid: u32) -> Result<BanStatus>{
+        let user = self.get_user_data(pid).await?;
+
+        // pretendo marks banned accounts with a negative access level
+        if user.access_level >= 0 {
+            return Ok(BanStatus::NotBanned);
+        }
+
+        Ok(BanStatus::Banned {
+            reason: None,
+            until: None,
+        })
+    }
 }
-*/
+
 #[cfg(test)]
 mod test{
-    use crate::grpc::account::Client;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use reqwest::Url;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Server;
+    use tonic::{Request, Response, Status};
+    use crate::grpc::account::{Client, Error, GrpcClient};
+    use crate::grpc::protobufs::account::account_server;
+    use crate::grpc::protobufs::account::{
+        ExchangeTokenForUserDataRequest, GetNexDataRequest, GetNexDataResponse,
+        GetNexPasswordRequest, GetNexPasswordResponse, GetUserDataRequest, GetUserDataResponse,
+        UpdatePnidPermissionsRequest
+    };
+    use crate::nex::account_provider::BanStatus;
+
+    /// Starts a http server which answers the requests it gets with the given responses in order,
+    /// requests without the right api key get a 401.
+    async fn mock_server(responses: Vec<(u16, &'static str)>) -> (Url, Arc<AtomicUsize>){
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+
+        let counter = hits.clone();
+
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+
+                // read until we have the headers and the full body
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+
+                    if n == 0 {
+                        break;
+                    }
+
+                    request.extend_from_slice(&buf[..n]);
+
+                    let text = String::from_utf8_lossy(&request).to_lowercase();
+
+                    if let Some(header_end) = text.find("\r\n\r\n") {
+                        let content_length: usize = text.lines()
+                            .find_map(|l| l.strip_prefix("content-length: "))
+                            .and_then(|l| l.trim().parse().ok())
+                            .unwrap_or(0);
+
+                        if request.len() >= header_end + 4 + content_length {
+                            break;
+                        }
+                    }
+                }
+
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let authorized = String::from_utf8_lossy(&request)
+                    .to_lowercase()
+                    .contains("x-api-key: test-key");
+
+                let (status, body) = if authorized { (status, body) } else { (401, "") };
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body
+                );
+
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (format!("http://{}/graphql", addr).parse().unwrap(), hits)
+    }
+
+    #[tokio::test]
+    async fn typed_responses(){
+        let (uri, _) = mock_server(vec![
+            (200, r#"{"data": {"userByPid": {"nexPassword": "abcdefghijklmnop"}}}"#),
+            (200, r#"{"data": {"userByUsername": {"pid": 1699562916}}}"#),
+            (200, r#"{"data": {"userByPid": null}}"#),
+        ]).await;
+
+        let client = Client::with_config(uri, "test-key").unwrap();
+
+        assert_eq!(&client.get_nex_password(1699562916).await.unwrap(), b"abcdefghijklmnop");
+        assert_eq!(client.get_pid_by_username("someone").await.unwrap(), 1699562916);
+        assert!(matches!(client.get_username_by_pid(1).await, Err(Error::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn graphql_errors_are_mapped(){
+        let (uri, _) = mock_server(vec![
+            (200, r#"{"data": null, "errors": [{"message": "no such user", "extensions": {"code": "NOT_FOUND"}}]}"#),
+            (200, r#"{"data": null, "errors": [{"message": "bad key", "extensions": {"code": "UNAUTHENTICATED"}}]}"#),
+            (200, r#"{"data": null, "errors": [{"message": "weird"}]}"#),
+        ]).await;
+
+        let client = Client::with_config(uri, "test-key").unwrap();
+
+        assert!(matches!(client.get_username_by_pid(1).await, Err(Error::NotFound)));
+        assert!(matches!(client.get_username_by_pid(1).await, Err(Error::Unauthorized)));
+        assert!(matches!(client.get_username_by_pid(1).await, Err(Error::GraphQl(_))));
+    }
+
+    #[tokio::test]
+    async fn ban_status(){
+        let (uri, hits) = mock_server(vec![
+            (200, r#"{"data": {"userByPid": {"banned": true, "banReason": "cheating"}}}"#),
+            (200, r#"{"data": {"userByPid": {}}}"#),
+        ]).await;
+
+        // without the ban fields the account server doesnt even get asked
+        let client = Client::with_config(uri.clone(), "test-key").unwrap();
+
+        assert_eq!(client.get_ban_status(1699562917).await.unwrap(), BanStatus::NotBanned);
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+        let client = client.with_ban_fields(true);
+
+        assert_eq!(client.get_ban_status(1699562917).await.unwrap(), BanStatus::Banned {
+            reason: Some("cheating".to_owned()),
+            until: None,
+        });
+        assert_eq!(client.get_ban_status(1699562916).await.unwrap(), BanStatus::NotBanned);
+    }
+
+    #[tokio::test]
+    async fn wrong_api_key_is_unauthorized(){
+        let (uri, _) = mock_server(vec![(200, "")]).await;
+
+        let client = Client::with_config(uri, "wrong-key").unwrap();
+
+        assert!(matches!(client.get_username_by_pid(1).await, Err(Error::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn retries_when_upstream_is_down(){
+        let (uri, hits) = mock_server(vec![
+            (503, ""),
+            (502, ""),
+            (200, r#"{"data": {"userByPid": {"username": "someone"}}}"#),
+        ]).await;
+
+        let client = Client::with_config(uri.clone(), "test-key").unwrap();
+
+        assert_eq!(client.get_username_by_pid(1699562916).await.unwrap(), "someone");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        let (uri, hits) = mock_server(vec![(503, ""), (503, ""), (503, "")]).await;
+
+        let client = Client::with_config(uri, "test-key").unwrap();
+
+        assert!(matches!(client.get_username_by_pid(1).await, Err(Error::UpstreamDown(_))));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    struct MockAccountService;
+
+    impl MockAccountService{
+        fn check_key<T>(request: &Request<T>) -> Result<(), Status>{
+            match request.metadata().get("x-api-key") {
+                Some(key) if key == "test-key" => Ok(()),
+                _ => Err(Status::unauthenticated("invalid api key")),
+            }
+        }
+    }
+
+    #[tonic::async_trait]
+    impl account_server::Account for MockAccountService{
+        async fn get_user_data(&self, request: Request<GetUserDataRequest>) -> Result<Response<GetUserDataResponse>, Status> {
+            Self::check_key(&request)?;
+
+            match request.into_inner().pid {
+                1699562916 => Ok(Response::new(GetUserDataResponse{
+                    pid: 1699562916,
+                    username: "someone".to_owned(),
+                    ..Default::default()
+                })),
+                1699562917 => Ok(Response::new(GetUserDataResponse{
+                    pid: 1699562917,
+                    username: "cheater".to_owned(),
+                    access_level: -1,
+                    ..Default::default()
+                })),
+                _ => Err(Status::not_found("no such user")),
+            }
+        }
+
+        async fn get_nex_password(&self, request: Request<GetNexPasswordRequest>) -> Result<Response<GetNexPasswordResponse>, Status> {
+            Self::check_key(&request)?;
+
+            Ok(Response::new(GetNexPasswordResponse{
+                password: "abcdefghijklmnop".to_owned(),
+            }))
+        }
+
+        async fn get_nex_data(&self, _request: Request<GetNexDataRequest>) -> Result<Response<GetNexDataResponse>, Status> {
+            Err(Status::unimplemented("not needed"))
+        }
+
+        async fn update_pnid_permissions(&self, _request: Request<UpdatePnidPermissionsRequest>) -> Result<Response<()>, Status> {
+            Err(Status::unimplemented("not needed"))
+        }
+
+        async fn exchange_token_for_user_data(&self, request: Request<ExchangeTokenForUserDataRequest>) -> Result<Response<GetUserDataResponse>, Status> {
+            Self::check_key(&request)?;
+
+            match request.into_inner().token.as_str() {
+                "valid-token" => Ok(Response::new(GetUserDataResponse{
+                    pid: 1699562916,
+                    username: "someone".to_owned(),
+                    ..Default::default()
+                })),
+                _ => Err(Status::not_found("invalid token")),
+            }
+        }
+    }
+
+    async fn mock_grpc_server() -> String{
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(
+            Server::builder()
+                .add_service(account_server::AccountServer::new(MockAccountService))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+        );
+
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn grpc_client(){
+        let uri = mock_grpc_server().await;
+
+        let client = GrpcClient::with_config(&uri, "test-key").unwrap();
+
+        assert_eq!(&client.get_nex_password(1699562916).await.unwrap(), b"abcdefghijklmnop");
+        assert_eq!(client.get_username_by_pid(1699562916).await.unwrap(), "someone");
+        assert_eq!(client.get_ban_status(1699562916).await.unwrap(), BanStatus::NotBanned);
+        assert!(client.get_ban_status(1699562917).await.unwrap().is_banned());
+        assert!(matches!(client.get_username_by_pid(1).await, Err(Error::NotFound)));
+        assert!(matches!(client.get_pid_by_username("someone").await, Err(Error::Unsupported(_))));
+        assert_eq!(client.get_pid_by_token("valid-token").await.unwrap(), 1699562916);
+        assert!(matches!(client.get_pid_by_token("someone").await, Err(Error::NotFound)));
+
+        let client = GrpcClient::with_config(&uri, "wrong-key").unwrap();
+
+        assert!(matches!(client.get_username_by_pid(1699562916).await, Err(Error::Unauthorized)));
+    }
 
     #[tokio::test]
     async fn test(){
         dotenv::dotenv().ok();
 
-        let mut client = Client::new().await.unwrap();
+        let client = Client::new().unwrap();
 
         let cli = client.get_nex_password(1699562916).await.unwrap();
 
@@ -153,4 +671,4 @@ mod test{
     }
 
 
-}
\ No newline at end of file
+}
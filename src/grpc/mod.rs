@@ -2,8 +2,27 @@
 //! before account rs is finished.
 //! 
 //! This WILL be deprecated as soon as account rs is in a stable state.
+use tonic::metadata::errors::InvalidMetadataValue;
+use tonic::metadata::AsciiMetadataValue;
+use tonic::service::Interceptor;
 use tonic::{Request, Status};
 
-type InterceptorFunc = Box<(dyn Fn(Request<()>) -> Result<Request<()>, Status> + Send)>;
 mod protobufs;
-pub mod account;
\ No newline at end of file
+pub mod account;
+
+/// Adds the `x-api-key` header to every request.
+#[derive(Clone)]
+pub struct ApiKeyInterceptor(AsciiMetadataValue);
+
+impl ApiKeyInterceptor{
+    pub fn new(api_key: &str) -> Result<Self, InvalidMetadataValue>{
+        Ok(Self(api_key.parse()?))
+    }
+}
+
+impl Interceptor for ApiKeyInterceptor{
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request.metadata_mut().insert("x-api-key", self.0.clone());
+        Ok(request)
+    }
+}
\ No newline at end of file
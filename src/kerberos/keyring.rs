@@ -0,0 +1,129 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::kerberos::KEY_CACHE;
+use crate::nex::account::{kerberos_password_from_str, Account};
+use crate::util::watch_file;
+
+/// Holds the passwords of a server account together with their derived keys.
+///
+/// The first entry is always the current password, which is the only one tickets get issued
+/// with. The other entries are previous passwords which are still accepted when decrypting
+/// tickets so that rotating the password doesnt invalidate every ticket which is still out there.
+pub struct ServerKeyring{
+    pid: u32,
+    keys: RwLock<Vec<([u8; 16], [u8; 16])>>,
+}
+
+impl ServerKeyring{
+    pub fn new(account: &Account, previous_passwords: &[[u8; 16]]) -> Self{
+        let keyring = Self{
+            pid: account.pid,
+            keys: RwLock::new(Vec::new()),
+        };
+
+        let mut passwords = vec![account.kerbros_password];
+        passwords.extend_from_slice(previous_passwords);
+
+        keyring.set_passwords(&passwords);
+
+        keyring
+    }
+
+    pub fn pid(&self) -> u32{
+        self.pid
+    }
+
+    /// The login data (pid and current password) to use when issuing tickets.
+    pub fn current_login_data(&self) -> (u32, [u8; 16]){
+        let keys = self.keys.read().expect("keyring poisoned");
+
+        (self.pid, keys[0].0)
+    }
+
+    /// All keys which are currently accepted, the current key comes first.
+    pub fn keys(&self) -> Vec<[u8; 16]>{
+        let keys = self.keys.read().expect("keyring poisoned");
+
+        keys.iter().map(|(_, key)| *key).collect()
+    }
+
+    /// Replaces the passwords of this keyring, the first password becomes the current one.
+    ///
+    /// This derives the keys on the current thread so dont call this from async code directly.
+    pub fn set_passwords(&self, passwords: &[[u8; 16]]){
+        assert!(!passwords.is_empty(), "a keyring needs at least a current password");
+
+        let new_keys = passwords.iter()
+            .map(|password| (*password, KEY_CACHE.derive_key_blocking(self.pid, *password)))
+            .collect();
+
+        let mut keys = self.keys.write().expect("keyring poisoned");
+
+        *keys = new_keys;
+    }
+
+    /// Reads the passwords from a file with one password per line, the first line being the
+    /// current password.
+    pub fn reload_from_file(&self, path: &Path) -> io::Result<()>{
+        let contents = fs::read_to_string(path)?;
+
+        let passwords = contents.lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(kerberos_password_from_str)
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if passwords.is_empty(){
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "keyring file contains no passwords"));
+        }
+
+        self.set_passwords(&passwords);
+
+        Ok(())
+    }
+
+    /// Watches the given keyring file and reloads the keys whenever it gets modified, this allows
+    /// introducing a new server password without having to restart anything.
+    pub fn spawn_reload_task(this: Arc<Self>, path: PathBuf, interval: Duration) -> JoinHandle<()>{
+        watch_file(path, interval, move |path| this.reload_from_file(path))
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::kerberos::derive_key;
+    use crate::kerberos::keyring::ServerKeyring;
+    use crate::nex::account::{kerberos_password_from_str, Account};
+
+    #[test]
+    fn rotation_keeps_previous_keys(){
+        let account = Account::new(2, "Quazal Rendez-Vous", "new");
+        let old_password = kerberos_password_from_str("old").unwrap();
+
+        let keyring = ServerKeyring::new(&account, &[old_password]);
+
+        assert_eq!(keyring.current_login_data(), (2, account.kerbros_password));
+        assert_eq!(keyring.keys(), vec![
+            derive_key(2, account.kerbros_password),
+            derive_key(2, old_password)
+        ]);
+    }
+
+    #[test]
+    fn reload_rejects_long_passwords(){
+        let account = Account::new(2, "Quazal Rendez-Vous", "current");
+        let keyring = ServerKeyring::new(&account, &[]);
+
+        let path = std::env::temp_dir().join(format!("rnex_keyring_{}", std::process::id()));
+        std::fs::write(&path, "new\nthis password is way too long\n").unwrap();
+
+        assert!(keyring.reload_from_file(&path).is_err());
+        assert_eq!(keyring.current_login_data(), (2, account.kerbros_password));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,14 +1,26 @@
+use std::env;
 use std::io::{Read, Write};
+use std::mem::size_of;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
 use bytemuck::{bytes_of, Pod, Zeroable};
-use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use std::fmt::{Display, Formatter};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeDelta, Timelike, Utc};
 use hmac::Hmac;
+use lru::LruCache;
 use md5::{Digest, Md5};
+use once_cell::sync::Lazy;
 use rc4::{Rc4, Rc4Core, StreamCipher};
 use rc4::cipher::StreamCipherCoreWrapper;
 use rc4::consts::U16;
 use hmac::Mac;
 use rc4::KeyInit;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
 use crate::rmc::structures::RmcSerialize;
+use crate::versions::NexVersion;
+
+pub mod keyring;
 
 type Md5Hmac = Hmac<md5::Md5>;
 
@@ -25,7 +37,96 @@ pub fn derive_key(pid: u32, password: [u8; 16]) -> [u8; 16]{
 
     key
 }
-#[derive(Pod, Zeroable, Copy, Clone, Debug, Eq, PartialEq, Default)]
+
+/// Cache of already derived kerberos keys keyed by pid.
+///
+/// Deriving a key takes 65000+ md5 iterations which is way too expensive to do on every connect
+/// or login, so the results are kept here. The password is stored alongside the key so that an
+/// entry gets thrown out as soon as the password of the account changes.
+pub struct DerivedKeyCache(Mutex<LruCache<u32, ([u8; 16], [u8; 16])>>);
+
+impl DerivedKeyCache{
+    pub fn new(capacity: NonZeroUsize) -> Self{
+        Self(Mutex::new(LruCache::new(capacity)))
+    }
+
+    /// Returns the cached key for this account if there is one for the given password.
+    pub fn get(&self, pid: u32, password: [u8; 16]) -> Option<[u8; 16]>{
+        let mut cache = self.0.lock().expect("key cache poisoned");
+
+        match cache.get(&pid){
+            Some((cached_password, key)) if *cached_password == password => Some(*key),
+            Some(_) => {
+                // the password changed since this was derived
+                cache.pop(&pid);
+                None
+            }
+            None => None
+        }
+    }
+
+    pub fn insert(&self, pid: u32, password: [u8; 16], key: [u8; 16]){
+        let mut cache = self.0.lock().expect("key cache poisoned");
+
+        cache.put(pid, (password, key));
+    }
+
+    /// Removes the cached key of an account, use this when the password of an account changes.
+    pub fn invalidate(&self, pid: u32){
+        let mut cache = self.0.lock().expect("key cache poisoned");
+
+        cache.pop(&pid);
+    }
+
+    /// Gets the key from the cache or derives it on the blocking thread pool so that the async
+    /// executor doesnt get stalled by the md5 iterations.
+    pub async fn derive_key(&self, pid: u32, password: [u8; 16]) -> [u8; 16]{
+        if let Some(key) = self.get(pid, password){
+            return key;
+        }
+
+        let key = tokio::task::spawn_blocking(move || derive_key(pid, password)).await
+            .expect("key derivation panicked");
+
+        self.insert(pid, password, key);
+
+        key
+    }
+
+    /// Same as [`Self::derive_key`] but runs the derivation on the current thread, only use this
+    /// outside of async code (e.g. when precomputing keys at startup).
+    pub fn derive_key_blocking(&self, pid: u32, password: [u8; 16]) -> [u8; 16]{
+        if let Some(key) = self.get(pid, password){
+            return key;
+        }
+
+        let key = derive_key(pid, password);
+
+        self.insert(pid, password, key);
+
+        key
+    }
+}
+
+pub static KEY_CACHE: Lazy<DerivedKeyCache> = Lazy::new(|| {
+    let capacity = env::var("KERBEROS_KEY_CACHE_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .and_then(NonZeroUsize::new)
+        .unwrap_or(NonZeroUsize::new(4096).unwrap());
+
+    DerivedKeyCache::new(capacity)
+});
+
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+#[error("invalid kerberos date time: {0:#x}")]
+pub struct InvalidDateTime(pub u64);
+
+/// Date time as it is used by nex, the individual components are packed into bitfields of a u64.
+///
+/// Because of the layout (year being in the highest bits and seconds in the lowest) comparing the
+/// raw values also compares the points in time.
+#[derive(Pod, Zeroable, Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
 #[repr(transparent)]
 pub struct KerberosDateTime(pub u64);
 
@@ -35,15 +136,7 @@ impl KerberosDateTime{
     }
 
     pub fn now() -> Self{
-        let now = chrono::Utc::now();
-        Self::new(
-            now.second() as u64,
-            now.minute() as u64,
-            now.hour() as u64,
-            now.day() as u64,
-            now.month() as u64,
-            now.year() as u64,
-        )
+        chrono::Utc::now().into()
     }
 
     #[inline]
@@ -74,11 +167,97 @@ impl KerberosDateTime{
         (self.0 >> 26) & 0xFFFFFFFF
     }
 
-    pub fn to_regular_time(&self) -> chrono::DateTime<Utc>{
-        NaiveDateTime::new(
-            NaiveDate::from_ymd_opt(self.get_year() as i32, self.get_month() as u32, self.get_days() as u32).unwrap(),
-            NaiveTime::from_hms_opt(self.get_hours() as u32, self.get_minutes() as u32, self.get_seconds() as u32).unwrap()
-        ).and_utc()
+    /// Converts this into a regular date time, this fails if any of the components are out of
+    /// range (e.g. a corrupted or forged timestamp).
+    pub fn to_regular_time(&self) -> Result<DateTime<Utc>, InvalidDateTime>{
+        (*self).try_into()
+    }
+
+    /// Returns the date time which is `duration` after this one or `None` if either this or the
+    /// result isnt a valid date time.
+    pub fn checked_add(&self, duration: TimeDelta) -> Option<Self>{
+        let time = self.to_regular_time().ok()?;
+
+        time.checked_add_signed(duration).map(Self::from)
+    }
+
+    /// Returns the date time which is `duration` before this one or `None` if either this or the
+    /// result isnt a valid date time.
+    pub fn checked_sub(&self, duration: TimeDelta) -> Option<Self>{
+        let time = self.to_regular_time().ok()?;
+
+        time.checked_sub_signed(duration).map(Self::from)
+    }
+
+    /// Time which passed from `earlier` to `self`, this is negative if `earlier` is actually later.
+    pub fn signed_duration_since(&self, earlier: KerberosDateTime) -> Result<TimeDelta, InvalidDateTime>{
+        Ok(self.to_regular_time()? - earlier.to_regular_time()?)
+    }
+
+    /// Checks if more than `lifetime` has passed since this point in time, invalid date times are
+    /// always treated as expired.
+    pub fn has_expired(&self, lifetime: TimeDelta) -> bool{
+        match self.checked_add(lifetime){
+            Some(expiry) => expiry < Self::now(),
+            None => true
+        }
+    }
+}
+
+impl TryFrom<KerberosDateTime> for DateTime<Utc>{
+    type Error = InvalidDateTime;
+
+    fn try_from(value: KerberosDateTime) -> Result<Self, Self::Error> {
+        let year = i32::try_from(value.get_year()).map_err(|_| InvalidDateTime(value.0))?;
+
+        let date = NaiveDate::from_ymd_opt(year, value.get_month() as u32, value.get_days() as u32)
+            .ok_or(InvalidDateTime(value.0))?;
+        let time = NaiveTime::from_hms_opt(value.get_hours() as u32, value.get_minutes() as u32, value.get_seconds() as u32)
+            .ok_or(InvalidDateTime(value.0))?;
+
+        Ok(NaiveDateTime::new(date, time).and_utc())
+    }
+}
+
+impl From<DateTime<Utc>> for KerberosDateTime{
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::new(
+            value.second() as u64,
+            value.minute() as u64,
+            value.hour() as u64,
+            value.day() as u64,
+            value.month() as u64,
+            value.year() as u64,
+        )
+    }
+}
+
+impl Display for KerberosDateTime{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.to_regular_time(){
+            Ok(time) => write!(f, "{}", time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            Err(_) => write!(f, "<invalid date time {:#x}>", self.0)
+        }
+    }
+}
+
+// the web api gets these as rfc3339 strings as nobody outside of nex knows what to do with the
+// packed format
+impl Serialize for KerberosDateTime{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let time = self.to_regular_time().map_err(serde::ser::Error::custom)?;
+
+        serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl<'de> Deserialize<'de> for KerberosDateTime{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let str = String::deserialize(deserializer)?;
+
+        let time = DateTime::parse_from_rfc3339(&str).map_err(serde::de::Error::custom)?;
+
+        Ok(time.with_timezone(&Utc).into())
     }
 }
 
@@ -92,6 +271,21 @@ impl RmcSerialize for KerberosDateTime{
     }
 }
 
+/// Checks if the md5-hmac at the end of `data` was made with `key`.
+pub fn verify_hmac(key: [u8; 16], data: &[u8]) -> bool{
+    if data.len() < 0x10{
+        return false;
+    }
+
+    let (data, signature) = data.split_at(data.len() - 0x10);
+
+    let mut hmac = <Md5Hmac as KeyInit>::new_from_slice(&key).unwrap();
+
+    hmac.update(data);
+
+    hmac.verify_slice(signature).is_ok()
+}
+
 #[derive(Pod, Zeroable, Copy, Clone)]
 #[repr(C, packed)]
 pub struct TicketInternalData{
@@ -101,7 +295,7 @@ pub struct TicketInternalData{
 }
 
 impl TicketInternalData{
-    pub(crate) fn new(pid: u32) -> Self{
+    pub fn new(pid: u32) -> Self{
         Self{
             issued_time: KerberosDateTime::now(),
             pid,
@@ -109,9 +303,33 @@ impl TicketInternalData{
         }
     }
 
-    pub(crate) fn encrypt(&self, key: [u8; 16]) -> Box<[u8]>{
+    /// Reads the decrypted ticket data, tickets for our own secure server may have the packed nex
+    /// version of the client after the regular data.
+    pub fn read(data: &[u8]) -> Option<(Self, Option<NexVersion>)>{
+        if data.len() < size_of::<Self>(){
+            return None;
+        }
+
+        let (ticket, extra) = data.split_at(size_of::<Self>());
+
+        let nex_version = match extra{
+            [] => None,
+            [a, b, c, d] => Some(NexVersion::from_packed(u32::from_le_bytes([*a, *b, *c, *d]))),
+            _ => return None,
+        };
+
+        Some((bytemuck::pod_read_unaligned(ticket), nex_version))
+    }
+
+    /// Encrypts the ticket for the server with `key`, the nex version should only be given for
+    /// our own secure server as other servers only know the regular layout.
+    pub fn encrypt(&self, key: [u8; 16], nex_version: Option<NexVersion>) -> Box<[u8]>{
         let mut data = bytes_of(self).to_vec();
 
+        if let Some(version) = nex_version{
+            data.extend_from_slice(&version.to_packed().to_le_bytes());
+        }
+
         let mut rc4: StreamCipherCoreWrapper<Rc4Core<U16>> = Rc4::new_from_slice(&key).unwrap();
         rc4.apply_keystream(&mut data);
 
@@ -158,13 +376,76 @@ impl Ticket{
 
 #[cfg(test)]
 mod test{
-    use chrono::{Datelike, Utc};
-    use crate::kerberos::KerberosDateTime;
+    use std::num::NonZeroUsize;
+    use chrono::{Datelike, TimeDelta, TimeZone, Utc};
+    use bytemuck::bytes_of;
+    use crate::kerberos::{derive_key, DerivedKeyCache, KerberosDateTime, TicketInternalData};
+    use crate::versions::NexVersion;
 
     #[test]
     fn kerberos_time_convert_test(){
         let time = KerberosDateTime(135904948834);
 
-        println!("{}", time.to_regular_time().to_rfc2822());
+        println!("{}", time.to_regular_time().unwrap().to_rfc2822());
+    }
+
+    #[test]
+    fn kerberos_time_round_trip(){
+        let time = Utc.with_ymd_and_hms(2025, 3, 14, 15, 9, 26).unwrap();
+
+        let kerberos_time: KerberosDateTime = time.into();
+
+        assert_eq!(kerberos_time.to_regular_time(), Ok(time));
+        assert_eq!(kerberos_time.to_string(), "2025-03-14T15:09:26Z");
+
+        let later = kerberos_time.checked_add(TimeDelta::hours(12)).unwrap();
+
+        assert!(later > kerberos_time);
+        assert_eq!(later.signed_duration_since(kerberos_time), Ok(TimeDelta::hours(12)));
+    }
+
+    #[test]
+    fn invalid_kerberos_time_doesnt_panic(){
+        // month 15 and day 0
+        let time = KerberosDateTime::new(0, 0, 0, 0, 15, 2025);
+
+        assert!(time.to_regular_time().is_err());
+        assert!(time.has_expired(TimeDelta::days(1)));
+    }
+
+    #[test]
+    fn key_cache_invalidates_on_password_change(){
+        let cache = DerivedKeyCache::new(NonZeroUsize::new(2).unwrap());
+
+        let old_password = *b"old password\0\0\0\0";
+        let new_password = *b"new password\0\0\0\0";
+
+        let key = cache.derive_key_blocking(1, old_password);
+
+        assert_eq!(key, derive_key(1, old_password));
+        assert_eq!(cache.get(1, old_password), Some(key));
+
+        assert_eq!(cache.get(1, new_password), None);
+        assert_eq!(cache.get(1, old_password), None);
+    }
+
+    #[test]
+    fn ticket_layouts(){
+        let ticket = TicketInternalData::new(2);
+        let data = bytes_of(&ticket);
+
+        // the regular layout which every nex server understands
+        assert_eq!(data.len(), 44);
+
+        let (read, version) = TicketInternalData::read(data).unwrap();
+        assert_eq!({ read.pid }, 2);
+        assert_eq!(version, None);
+
+        let extended = [data, &30500u32.to_le_bytes()].concat();
+        let (_, version) = TicketInternalData::read(&extended).unwrap();
+        assert_eq!(version, Some(NexVersion::new(3, 5, 0)));
+
+        assert!(TicketInternalData::read(&data[..40]).is_none());
+        assert!(TicketInternalData::read(&[data, &[0, 0]].concat()).is_none());
     }
 }
\ No newline at end of file
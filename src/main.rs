@@ -253,9 +253,9 @@ async fn start_secure_server() -> SecureServer{
             Box::new(|p, count|{
                 Box::pin(
                     async move {
-                        let (session_key, pid, check_value) = read_secure_connection_data(&p.payload, &SECURE_SERVER_ACCOUNT)?;
+                        let (session_key, pid, check_value, _) = read_secure_connection_data(&p.payload, &SECURE_SERVER_ACCOUNT)?;
 
-                        let check_value_response = check_value + 1;
+                        let check_value_response = check_value.wrapping_add(1);
 
                         let data = bytemuck::bytes_of(&check_value_response);
 
@@ -334,7 +334,15 @@ async fn start_secure() -> JoinHandle<()> {
 
         MatchmakeManager::initialize_garbage_collect_thread(weak_mmm).await;
 
-        let web_server = web::start_web(mmm.clone()).await;
+        // the web api is the only thing here which needs the account backend, so the secure
+        // server still runs without one
+        let web_server = match nex::account_provider::AccountBackend::from_env() {
+            Ok(accounts) => Some(web::start_web(mmm.clone(), Arc::new(accounts)).await),
+            Err(e) => {
+                error!("unable to set up account backend, not starting the web api: {}", e);
+                None
+            }
+        };
 
         let (router_secure, _) =
             Router::new(SocketAddrV4::new(*OWN_IP_PRIVATE, *SECURE_SERVER_PORT))
@@ -369,6 +377,7 @@ async fn start_secure() -> JoinHandle<()> {
                 Arc::new_cyclic(|w| User {
                     ip,
                     pid,
+                    guest: pid == nex::account::GUEST_PID,
                     this: w.clone(),
                     remote: RemoteConsole::new(r),
                     station_url: Default::default(),
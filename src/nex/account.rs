@@ -1,5 +1,11 @@
+use std::io;
 use macros::RmcSerialize;
 
+/// The well known guest account which some titles (and our test clients) log in with.
+pub const GUEST_PID: u32 = 100;
+pub const GUEST_USERNAME: &str = "guest";
+pub const GUEST_PASSWORD: &str = "MMQea3n!fsik";
+
 #[derive(RmcSerialize)]
 #[derive(Clone)]
 pub struct Account{
@@ -8,21 +14,35 @@ pub struct Account{
     pub kerbros_password: [u8; 16],
 }
 
-impl Account{
-    pub fn new(pid: u32, username: &str, passwd: &str) -> Self{
-        let passwd_data = passwd.as_bytes();
+/// Converts a plain text password into the fixed size form used for kerberos, passwords longer
+/// than 16 bytes dont fit into it and are rejected.
+pub fn kerberos_password_from_str(passwd: &str) -> io::Result<[u8; 16]>{
+    let passwd_data = passwd.as_bytes();
 
-        let mut passwd = [0u8; 16];
+    if passwd_data.len() > 16{
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "kerberos passwords cant be longer than 16 bytes"));
+    }
 
-        for (idx, byte) in passwd_data.iter().enumerate(){
-            passwd[idx] = *byte;
-        }
+    let mut passwd = [0u8; 16];
 
-        Self{
-            kerbros_password: passwd,
+    passwd[..passwd_data.len()].copy_from_slice(passwd_data);
+
+    Ok(passwd)
+}
+
+impl Account{
+    /// Panics if the password is too long, use [`Account::try_new`] for passwords which dont come
+    /// from the server config.
+    pub fn new(pid: u32, username: &str, passwd: &str) -> Self{
+        Self::try_new(pid, username, passwd).expect("invalid account password")
+    }
+
+    pub fn try_new(pid: u32, username: &str, passwd: &str) -> io::Result<Self>{
+        Ok(Self{
+            kerbros_password: kerberos_password_from_str(passwd)?,
             username: username.into(),
             pid
-        }
+        })
     }
 
     pub fn new_raw_password(pid: u32, username: &str, passwd: [u8; 16]) -> Self{
@@ -33,6 +53,10 @@ impl Account{
         }
     }
 
+    pub fn guest() -> Self{
+        Self::new(GUEST_PID, GUEST_USERNAME, GUEST_PASSWORD)
+    }
+
     pub fn get_login_data(&self) -> (u32, [u8; 16]){
         (self.pid, self.kerbros_password)
     }
@@ -0,0 +1,172 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use log::{error, warn};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use crate::nex::account_provider::{self, AccountProvider};
+use crate::rmc::response::ErrorCode;
+
+/// How long resolved names and pids are kept before asking the account backend again.
+const LOOKUP_TTL: Duration = Duration::from_secs(60 * 10);
+
+/// Maps account backend errors to rmc errors, `not_found` is used when the account doesn't exist.
+///
+/// Lookups the backend cant do at all (like name lookups with the grpc backend) also count as the
+/// account not existing, so clients get a proper error instead of `Core_Exception`.
+pub(crate) fn map_account_error(error: account_provider::Error, not_found: ErrorCode) -> ErrorCode{
+    match error{
+        account_provider::Error::NotFound => not_found,
+        account_provider::Error::Unsupported(what) => {
+            warn!("the account backend doesnt support {}", what);
+            not_found
+        }
+        e => {
+            error!("error whilest talking to the account backend: {}", e);
+            ErrorCode::Core_Exception
+        }
+    }
+}
+
+/// Cache for resolving usernames to pids and the other way around, titles tend to request the
+/// names of the same few friends over and over again.
+pub struct AccountLookupCache{
+    names: Mutex<LruCache<u32, (String, Instant)>>,
+    pids: Mutex<LruCache<String, (u32, Instant)>>,
+    ttl: Duration,
+}
+
+impl AccountLookupCache{
+    pub fn new(capacity: NonZeroUsize) -> Self{
+        Self::with_ttl(capacity, LOOKUP_TTL)
+    }
+
+    pub fn with_ttl(capacity: NonZeroUsize, ttl: Duration) -> Self{
+        Self{
+            names: Mutex::new(LruCache::new(capacity)),
+            pids: Mutex::new(LruCache::new(capacity)),
+            ttl,
+        }
+    }
+
+    fn cache_pair(&self, pid: u32, name: &str){
+        let now = Instant::now();
+
+        self.names.lock().expect("lookup cache poisoned").put(pid, (name.to_owned(), now));
+        self.pids.lock().expect("lookup cache poisoned").put(name.to_owned(), (pid, now));
+    }
+
+    /// Gets the pid of the account with the given username.
+    pub async fn get_pid(&self, provider: &impl AccountProvider, username: &str) -> Result<u32, ErrorCode>{
+        {
+            let mut pids = self.pids.lock().expect("lookup cache poisoned");
+
+            if let Some((pid, fetched_at)) = pids.get(username) {
+                if fetched_at.elapsed() < self.ttl {
+                    return Ok(*pid);
+                }
+            }
+        }
+
+        let pid = provider.get_pid_by_name(username).await
+            .map_err(|e| map_account_error(e, ErrorCode::RendezVous_InvalidUsername))?;
+
+        self.cache_pair(pid, username);
+
+        Ok(pid)
+    }
+
+    /// Gets the username of the account with the given pid.
+    pub async fn get_name(&self, provider: &impl AccountProvider, pid: u32) -> Result<String, ErrorCode>{
+        {
+            let mut names = self.names.lock().expect("lookup cache poisoned");
+
+            if let Some((name, fetched_at)) = names.get(&pid) {
+                if fetched_at.elapsed() < self.ttl {
+                    return Ok(name.clone());
+                }
+            }
+        }
+
+        let name = provider.get_name_by_pid(pid).await
+            .map_err(|e| map_account_error(e, ErrorCode::RendezVous_InvalidPID))?;
+
+        self.cache_pair(pid, &name);
+
+        Ok(name)
+    }
+}
+
+pub static ACCOUNT_LOOKUP_CACHE: Lazy<AccountLookupCache> =
+    Lazy::new(|| AccountLookupCache::new(NonZeroUsize::new(4096).unwrap()));
+
+#[cfg(test)]
+mod test{
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+    use crate::nex::account_lookup::AccountLookupCache;
+    use crate::nex::account_provider::{AccountProvider, BanStatus, Error, Result};
+    use crate::rmc::response::ErrorCode;
+
+    /// Knows a single account and counts how often it got asked.
+    #[derive(Default)]
+    struct CountingProvider{
+        lookups: AtomicU32,
+    }
+
+    impl AccountProvider for CountingProvider{
+        async fn get_nex_password(&self, _pid: u32) -> Result<[u8; 16]> {
+            Err(Error::NotFound)
+        }
+
+        async fn get_pid_by_name(&self, username: &str) -> Result<u32> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+
+            match username {
+                "user" => Ok(1234),
+                "unsupported" => Err(Error::Unsupported("name lookups")),
+                _ => Err(Error::NotFound),
+            }
+        }
+
+        async fn get_name_by_pid(&self, pid: u32) -> Result<String> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+
+            match pid {
+                1234 => Ok("user".to_owned()),
+                _ => Err(Error::NotFound),
+            }
+        }
+
+        async fn get_ban_status(&self, _pid: u32) -> Result<BanStatus> {
+            Ok(BanStatus::NotBanned)
+        }
+    }
+
+    #[tokio::test]
+    async fn lookups_get_cached(){
+        let provider = CountingProvider::default();
+        let cache = AccountLookupCache::new(NonZeroUsize::new(16).unwrap());
+
+        assert_eq!(cache.get_pid(&provider, "user").await, Ok(1234));
+        // the name got cached together with the pid
+        assert_eq!(cache.get_name(&provider, 1234).await, Ok("user".to_owned()));
+        assert_eq!(cache.get_pid(&provider, "user").await, Ok(1234));
+        assert_eq!(provider.lookups.load(Ordering::Relaxed), 1);
+
+        assert_eq!(cache.get_name(&provider, 1).await, Err(ErrorCode::RendezVous_InvalidPID));
+        assert_eq!(cache.get_pid(&provider, "nobody").await, Err(ErrorCode::RendezVous_InvalidUsername));
+        assert_eq!(cache.get_pid(&provider, "unsupported").await, Err(ErrorCode::RendezVous_InvalidUsername));
+    }
+
+    #[tokio::test]
+    async fn entries_expire(){
+        let provider = CountingProvider::default();
+        let cache = AccountLookupCache::with_ttl(NonZeroUsize::new(16).unwrap(), Duration::ZERO);
+
+        assert_eq!(cache.get_name(&provider, 1234).await, Ok("user".to_owned()));
+        assert_eq!(cache.get_name(&provider, 1234).await, Ok("user".to_owned()));
+        assert_eq!(provider.lookups.load(Ordering::Relaxed), 2);
+    }
+}
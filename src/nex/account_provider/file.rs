@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use crate::nex::account::kerberos_password_from_str;
+use crate::nex::account_provider::{AccountProvider, BanStatus, Error, Result};
+
+#[derive(Deserialize)]
+struct FileAccount{
+    pid: u32,
+    username: String,
+    nex_password: String,
+    #[serde(default)]
+    banned: bool,
+    ban_reason: Option<String>,
+    ban_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct AccountFile{
+    #[serde(default)]
+    accounts: Vec<FileAccount>,
+}
+
+/// Account provider backed by a static list of accounts, this is mostly meant for running
+/// locally and for tests.
+///
+/// the file is either toml or json (decided by the extension) and looks like this:
+/// ```toml
+/// [[accounts]]
+/// pid = 1699562916
+/// username = "someone"
+/// nex_password = "abcdefghijklmnop"
+/// # optional
+/// banned = true
+/// ban_reason = "cheating"
+/// ban_until = "2030-01-01T00:00:00Z"
+/// ```
+pub struct FileAccountProvider{
+    accounts: HashMap<u32, FileAccount>,
+    pids: HashMap<String, u32>,
+}
+
+impl FileAccountProvider{
+    pub fn load(path: &Path) -> Result<Self>{
+        let contents = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&contents),
+            _ => Self::from_toml(&contents),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self>{
+        let file: AccountFile = toml::from_str(contents)
+            .map_err(|e| Error::FileFormat(e.to_string()))?;
+
+        Ok(Self::from_accounts(file.accounts))
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self>{
+        let file: AccountFile = serde_json::from_str(contents)
+            .map_err(|e| Error::FileFormat(e.to_string()))?;
+
+        Ok(Self::from_accounts(file.accounts))
+    }
+
+    fn from_accounts(accounts: Vec<FileAccount>) -> Self{
+        let pids = accounts.iter().map(|a| (a.username.clone(), a.pid)).collect();
+        let accounts = accounts.into_iter().map(|a| (a.pid, a)).collect();
+
+        Self{
+            accounts,
+            pids,
+        }
+    }
+
+    fn get(&self, pid: u32) -> Result<&FileAccount>{
+        self.accounts.get(&pid).ok_or(Error::NotFound)
+    }
+}
+
+impl AccountProvider for FileAccountProvider{
+    async fn get_nex_password(&self, pid: u32) -> Result<[u8; 16]> {
+        Ok(kerberos_password_from_str(&self.get(pid)?.nex_password)?)
+    }
+
+    async fn get_pid_by_name(&self, username: &str) -> Result<u32> {
+        self.pids.get(username).copied().ok_or(Error::NotFound)
+    }
+
+    async fn get_name_by_pid(&self, pid: u32) -> Result<String> {
+        Ok(self.get(pid)?.username.clone())
+    }
+
+    async fn get_ban_status(&self, pid: u32) -> Result<BanStatus> {
+        let account = self.get(pid)?;
+
+        if !account.banned {
+            return Ok(BanStatus::NotBanned);
+        }
+
+        Ok(BanStatus::Banned {
+            reason: account.ban_reason.clone(),
+            until: account.ban_until,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::nex::account::kerberos_password_from_str;
+    use crate::nex::account_provider::file::FileAccountProvider;
+    use crate::nex::account_provider::{AccountProvider, BanStatus, Error};
+
+    #[tokio::test]
+    async fn toml_and_json_accounts(){
+        let toml = FileAccountProvider::from_toml(r#"
+            [[accounts]]
+            pid = 1699562916
+            username = "someone"
+            nex_password = "abcdefghijklmnop"
+
+            [[accounts]]
+            pid = 1699562917
+            username = "cheater"
+            nex_password = "ponmlkjihgfedcba"
+            banned = true
+            ban_reason = "cheating"
+        "#).unwrap();
+
+        let json = FileAccountProvider::from_json(r#"{"accounts": [
+            {"pid": 1699562916, "username": "someone", "nex_password": "abcdefghijklmnop"}
+        ]}"#).unwrap();
+
+        for provider in [&toml, &json] {
+            assert_eq!(provider.get_pid_by_name("someone").await.unwrap(), 1699562916);
+            assert_eq!(provider.get_name_by_pid(1699562916).await.unwrap(), "someone");
+            assert_eq!(
+                provider.get_nex_password(1699562916).await.unwrap(),
+                kerberos_password_from_str("abcdefghijklmnop").unwrap()
+            );
+            assert_eq!(provider.get_ban_status(1699562916).await.unwrap(), BanStatus::NotBanned);
+            assert!(matches!(provider.get_pid_by_name("nobody").await, Err(Error::NotFound)));
+        }
+
+        assert!(toml.get_ban_status(1699562917).await.unwrap().is_banned());
+    }
+}
@@ -0,0 +1,24 @@
+use crate::grpc::account;
+use crate::nex::account_provider::{AccountProvider, BanStatus, Result};
+
+impl AccountProvider for account::GrpcClient{
+    async fn get_nex_password(&self, pid: u32) -> Result<[u8; 16]> {
+        Ok(account::GrpcClient::get_nex_password(self, pid).await?)
+    }
+
+    async fn get_pid_by_name(&self, username: &str) -> Result<u32> {
+        Ok(self.get_pid_by_username(username).await?)
+    }
+
+    async fn get_name_by_pid(&self, pid: u32) -> Result<String> {
+        Ok(self.get_username_by_pid(pid).await?)
+    }
+
+    async fn get_pid_by_token(&self, token: &str) -> Result<u32> {
+        Ok(account::GrpcClient::get_pid_by_token(self, token).await?)
+    }
+
+    async fn get_ban_status(&self, pid: u32) -> Result<BanStatus> {
+        Ok(account::GrpcClient::get_ban_status(self, pid).await?)
+    }
+}
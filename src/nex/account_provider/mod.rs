@@ -0,0 +1,174 @@
+//! Backends which the auth server can get account information from.
+//!
+//! note: async fn in traits cant be used dynamically, which is why the backend gets selected with
+//! the [`AccountBackend`] enum instead of a `dyn AccountProvider`.
+
+pub mod file;
+pub mod graphql;
+pub mod grpc;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use std::env;
+use std::path::PathBuf;
+use std::{io, result};
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use crate::grpc::account;
+
+#[derive(Error, Debug)]
+pub enum Error{
+    #[error("the requested user doesn't exist")]
+    NotFound,
+    #[error(transparent)]
+    AccountServer(account::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("unable to parse account file: {0}")]
+    FileFormat(String),
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("invalid account backend configuration: {0}")]
+    Config(String),
+    #[error("the account backend doesnt support {0}")]
+    Unsupported(&'static str),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl From<account::Error> for Error{
+    fn from(value: account::Error) -> Self {
+        match value {
+            account::Error::NotFound => Error::NotFound,
+            account::Error::Unsupported(what) => Error::Unsupported(what),
+            e => Error::AccountServer(e),
+        }
+    }
+}
+
+/// Whether an account is allowed to log in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum BanStatus{
+    #[default]
+    NotBanned,
+    Banned{
+        reason: Option<String>,
+        /// when the ban runs out, `None` means it's permanent
+        until: Option<DateTime<Utc>>,
+    }
+}
+
+impl BanStatus{
+    /// Checks if the account is banned right now, bans which already ran out dont count.
+    pub fn is_banned(&self) -> bool{
+        match self {
+            BanStatus::NotBanned => false,
+            BanStatus::Banned { until, .. } => until.map_or(true, |until| until > Utc::now()),
+        }
+    }
+}
+
+pub trait AccountProvider: Send + Sync{
+    /// Gets the nex password of the account, this is what the kerberos keys get derived from.
+    async fn get_nex_password(&self, pid: u32) -> Result<[u8; 16]>;
+    async fn get_pid_by_name(&self, username: &str) -> Result<u32>;
+    async fn get_name_by_pid(&self, pid: u32) -> Result<String>;
+    /// Gets the pid of the account a nnid/pnid token was issued for, only the grpc backend can
+    /// check tokens.
+    async fn get_pid_by_token(&self, _token: &str) -> Result<u32>{
+        Err(Error::Unsupported("logging in with tokens"))
+    }
+    async fn get_ban_status(&self, pid: u32) -> Result<BanStatus>;
+}
+
+/// The account backends we ship, selected at startup with `ACCOUNT_BACKEND`.
+pub enum AccountBackend{
+    GraphQl(account::Client),
+    Grpc(account::GrpcClient),
+    File(file::FileAccountProvider),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlite::SqliteAccountProvider),
+}
+
+impl AccountBackend{
+    /// Creates the backend selected by `ACCOUNT_BACKEND`, which is one of `graphql` (the default),
+    /// `grpc` (pretendos account service), `file` (reads `ACCOUNT_FILE`) or `sqlite` (opens
+    /// `ACCOUNT_SQLITE_PATH`).
+    pub fn from_env() -> Result<Self>{
+        let backend = env::var("ACCOUNT_BACKEND").unwrap_or("graphql".to_owned());
+
+        match backend.as_str() {
+            "graphql" => Ok(Self::GraphQl(account::Client::new()?)),
+            "grpc" => Ok(Self::Grpc(account::GrpcClient::new()?)),
+            "file" => {
+                let Ok(path) = env::var("ACCOUNT_FILE") else {
+                    return Err(Error::Config("ACCOUNT_FILE not set".to_owned()));
+                };
+
+                Ok(Self::File(file::FileAccountProvider::load(&PathBuf::from(path))?))
+            }
+            #[cfg(feature = "sqlite")]
+            "sqlite" => {
+                let Ok(path) = env::var("ACCOUNT_SQLITE_PATH") else {
+                    return Err(Error::Config("ACCOUNT_SQLITE_PATH not set".to_owned()));
+                };
+
+                Ok(Self::Sqlite(sqlite::SqliteAccountProvider::open(&PathBuf::from(path))?))
+            }
+            other => Err(Error::Config(format!("unknown account backend: {}", other))),
+        }
+    }
+}
+
+impl AccountProvider for AccountBackend{
+    async fn get_nex_password(&self, pid: u32) -> Result<[u8; 16]> {
+        match self {
+            Self::GraphQl(p) => AccountProvider::get_nex_password(p, pid).await,
+            Self::Grpc(p) => AccountProvider::get_nex_password(p, pid).await,
+            Self::File(p) => AccountProvider::get_nex_password(p, pid).await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(p) => AccountProvider::get_nex_password(p, pid).await,
+        }
+    }
+
+    async fn get_pid_by_name(&self, username: &str) -> Result<u32> {
+        match self {
+            Self::GraphQl(p) => AccountProvider::get_pid_by_name(p, username).await,
+            Self::Grpc(p) => AccountProvider::get_pid_by_name(p, username).await,
+            Self::File(p) => AccountProvider::get_pid_by_name(p, username).await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(p) => AccountProvider::get_pid_by_name(p, username).await,
+        }
+    }
+
+    async fn get_name_by_pid(&self, pid: u32) -> Result<String> {
+        match self {
+            Self::GraphQl(p) => AccountProvider::get_name_by_pid(p, pid).await,
+            Self::Grpc(p) => AccountProvider::get_name_by_pid(p, pid).await,
+            Self::File(p) => AccountProvider::get_name_by_pid(p, pid).await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(p) => AccountProvider::get_name_by_pid(p, pid).await,
+        }
+    }
+
+    async fn get_pid_by_token(&self, token: &str) -> Result<u32> {
+        match self {
+            Self::GraphQl(p) => AccountProvider::get_pid_by_token(p, token).await,
+            Self::Grpc(p) => AccountProvider::get_pid_by_token(p, token).await,
+            Self::File(p) => AccountProvider::get_pid_by_token(p, token).await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(p) => AccountProvider::get_pid_by_token(p, token).await,
+        }
+    }
+
+    async fn get_ban_status(&self, pid: u32) -> Result<BanStatus> {
+        match self {
+            Self::GraphQl(p) => AccountProvider::get_ban_status(p, pid).await,
+            Self::Grpc(p) => AccountProvider::get_ban_status(p, pid).await,
+            Self::File(p) => AccountProvider::get_ban_status(p, pid).await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(p) => AccountProvider::get_ban_status(p, pid).await,
+        }
+    }
+}
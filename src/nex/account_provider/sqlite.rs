@@ -0,0 +1,146 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::nex::account::kerberos_password_from_str;
+use crate::nex::account_provider::{AccountProvider, BanStatus, Error, Result};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS accounts (
+        pid INTEGER PRIMARY KEY NOT NULL,
+        username TEXT NOT NULL UNIQUE,
+        nex_password TEXT NOT NULL,
+        banned INTEGER NOT NULL DEFAULT 0,
+        ban_reason TEXT,
+        ban_until TEXT
+    );
+";
+
+/// Account provider backed by an embedded sqlite database, the `accounts` table gets created if
+/// it doesnt exist yet.
+pub struct SqliteAccountProvider{
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteAccountProvider{
+    pub fn open(path: &Path) -> Result<Self>{
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self>{
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self>{
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self{
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    pub fn add_account(&self, pid: u32, username: &str, nex_password: &str) -> Result<()>{
+        let conn = self.conn.lock().expect("sqlite connection poisoned");
+
+        conn.execute(
+            "INSERT INTO accounts (pid, username, nex_password) VALUES (?1, ?2, ?3)",
+            params![pid, username, nex_password]
+        )?;
+
+        Ok(())
+    }
+
+    /// Runs a query on the blocking thread pool as sqlite calls block.
+    async fn query<T: Send + 'static>(
+        &self,
+        func: impl FnOnce(&Connection) -> rusqlite::Result<Option<T>> + Send + 'static
+    ) -> Result<T>{
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().expect("sqlite connection poisoned");
+
+            func(&conn)
+        }).await
+            .expect("sqlite query panicked")?
+            .ok_or(Error::NotFound)
+    }
+}
+
+impl AccountProvider for SqliteAccountProvider{
+    async fn get_nex_password(&self, pid: u32) -> Result<[u8; 16]> {
+        let password: String = self.query(move |conn| {
+            conn.query_row(
+                "SELECT nex_password FROM accounts WHERE pid = ?1",
+                params![pid],
+                |row| row.get(0)
+            ).optional()
+        }).await?;
+
+        Ok(kerberos_password_from_str(&password)?)
+    }
+
+    async fn get_pid_by_name(&self, username: &str) -> Result<u32> {
+        let username = username.to_owned();
+
+        self.query(move |conn| {
+            conn.query_row(
+                "SELECT pid FROM accounts WHERE username = ?1",
+                params![username],
+                |row| row.get(0)
+            ).optional()
+        }).await
+    }
+
+    async fn get_name_by_pid(&self, pid: u32) -> Result<String> {
+        self.query(move |conn| {
+            conn.query_row(
+                "SELECT username FROM accounts WHERE pid = ?1",
+                params![pid],
+                |row| row.get(0)
+            ).optional()
+        }).await
+    }
+
+    async fn get_ban_status(&self, pid: u32) -> Result<BanStatus> {
+        let (banned, reason, until): (bool, Option<String>, Option<DateTime<Utc>>) = self.query(move |conn| {
+            conn.query_row(
+                "SELECT banned, ban_reason, ban_until FROM accounts WHERE pid = ?1",
+                params![pid],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            ).optional()
+        }).await?;
+
+        if !banned {
+            return Ok(BanStatus::NotBanned);
+        }
+
+        Ok(BanStatus::Banned {
+            reason,
+            until,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::nex::account::kerberos_password_from_str;
+    use crate::nex::account_provider::sqlite::SqliteAccountProvider;
+    use crate::nex::account_provider::{AccountProvider, BanStatus, Error};
+
+    #[tokio::test]
+    async fn sqlite_accounts(){
+        let provider = SqliteAccountProvider::open_in_memory().unwrap();
+
+        provider.add_account(1699562916, "someone", "abcdefghijklmnop").unwrap();
+
+        assert_eq!(provider.get_pid_by_name("someone").await.unwrap(), 1699562916);
+        assert_eq!(provider.get_name_by_pid(1699562916).await.unwrap(), "someone");
+        assert_eq!(
+            provider.get_nex_password(1699562916).await.unwrap(),
+            kerberos_password_from_str("abcdefghijklmnop").unwrap()
+        );
+        assert_eq!(provider.get_ban_status(1699562916).await.unwrap(), BanStatus::NotBanned);
+        assert!(matches!(provider.get_name_by_pid(1).await, Err(Error::NotFound)));
+    }
+}
@@ -1,19 +1,32 @@
 use std::hash::{DefaultHasher, Hasher};
+use std::ops::RangeInclusive;
 use std::net::SocketAddrV4;
 use std::sync::Arc;
-use crate::grpc::account;
-use crate::kerberos::{derive_key, KerberosDateTime, Ticket};
-use crate::nex::account::Account;
+use crate::kerberos::{KerberosDateTime, Ticket, KEY_CACHE};
+use crate::kerberos::keyring::ServerKeyring;
+use crate::nex::account_lookup::{map_account_error, ACCOUNT_LOOKUP_CACHE};
+use crate::nex::account_provider::{self, AccountBackend, AccountProvider};
+use crate::nex::account::{Account, GUEST_PID, GUEST_USERNAME};
+use crate::nex::moderation::Moderation;
+use crate::nex::service_accounts::ServiceAccountRegistry;
+use crate::prudp::sockaddr::PRUDPSockAddr;
 use crate::rmc::protocols::auth::{Auth, RawAuth, RawAuthInfo, RemoteAuth};
 use crate::rmc::response::ErrorCode;
 use crate::rmc::response::ErrorCode::Core_Unknown;
 use crate::rmc::structures::any::Any;
-use crate::rmc::structures::connection_data::ConnectionData;
+use crate::rmc::structures::authentication_info::AuthenticationInfo;
+use crate::rmc::structures::connection_data::{ConnectionData, VersionedConnectionData};
+use crate::rmc::structures;
 use crate::rmc::structures::qresult::QResult;
 use crate::{define_rmc_proto, kerberos};
+use log::{debug, error};
 use macros::rmc_struct;
-use crate::reggie::{RemoteEdgeNodeHolder, RemoteEdgeNodeManagement};
+use crate::prudp::station_url::{StationUrl, Type};
+use crate::prudp::station_url::UrlOptions::{Address, ConnectionID, NatType, Port, PrincipalID, StreamID, StreamType};
+use crate::reggie::{EdgeNodeInfo, RemoteEdgeNodeHolder, RemoteEdgeNodeManagement};
 use crate::rmc::protocols::OnlyRemote;
+use crate::versions::NexVersion;
+use tokio::sync::RwLock;
 
 define_rmc_proto!(
     proto AuthClientProtocol{
@@ -23,22 +36,70 @@ define_rmc_proto!(
 
 #[rmc_struct(AuthClientProtocol)]
 pub struct AuthHandler {
-    pub destination_server_acct: &'static Account,
+    pub accounts: Arc<AccountBackend>,
+    pub moderation: Arc<Moderation>,
+    /// the address of the connected client, if the proxy told us about it
+    pub client_addr: Option<PRUDPSockAddr>,
+    pub destination_server_keys: Arc<ServerKeyring>,
+    /// other nex services which tickets can be requested for
+    pub service_accounts: Arc<ServiceAccountRegistry>,
+    /// whether logging in with the guest account is allowed
+    pub allow_guest_login: bool,
+    pub connection_data_config: Arc<ConnectionDataConfig>,
     pub build_name: &'static str,
     //pub station_url: &'static str,
     pub control_server: Arc<OnlyRemote<RemoteEdgeNodeHolder>>,
+    /// the nex version the client told us about in `LoginWithContext`
+    pub nex_version: RwLock<Option<NexVersion>>,
 }
 
-pub fn generate_ticket(
+/// Special protocols and special station url handed out in the `ConnectionData` on login, edge
+/// nodes which advertise their own override these.
+#[derive(Default, Clone)]
+pub struct ConnectionDataConfig {
+    pub special_protocols: Vec<u8>,
+    pub special_station_url: Option<StationUrl>,
+}
+
+impl ConnectionDataConfig {
+    fn build(&self, secure_server_pid: u32, node: EdgeNodeInfo) -> ConnectionData {
+        let special_protocols = if node.special_protocols.is_empty() {
+            self.special_protocols.clone()
+        } else {
+            node.special_protocols
+        };
+
+        let special_station_url = if node.special_station_url.is_empty() {
+            self.special_station_url.as_ref().map(|u| u.to_string()).unwrap_or_default()
+        } else {
+            node.special_station_url
+        };
+
+        ConnectionData {
+            station_url: secure_station_url(secure_server_pid, node.address).to_string(),
+            special_protocols,
+            special_station_url,
+            date_time: KerberosDateTime::now(),
+        }
+    }
+}
+
+/// NGS versions of `AuthenticationInfo` which we know how to handle.
+const SUPPORTED_NGS_VERSIONS: RangeInclusive<u8> = 2..=4;
+
+/// Generates a ticket for the source account to the destination account, `nex_version` is passed
+/// on inside the ticket so it should only be given if the destination is our own secure server.
+pub async fn generate_ticket(
     source_act_login_data: (u32, [u8; 16]),
     dest_act_login_data: (u32, [u8; 16]),
+    nex_version: Option<NexVersion>,
 ) -> Box<[u8]> {
-    let source_key = derive_key(source_act_login_data.0, source_act_login_data.1);
-    let dest_key = derive_key(dest_act_login_data.0, dest_act_login_data.1);
+    let source_key = KEY_CACHE.derive_key(source_act_login_data.0, source_act_login_data.1).await;
+    let dest_key = KEY_CACHE.derive_key(dest_act_login_data.0, dest_act_login_data.1).await;
 
     let internal_data = kerberos::TicketInternalData::new(source_act_login_data.0);
 
-    let encrypted_inner = internal_data.encrypt(dest_key);
+    let encrypted_inner = internal_data.encrypt(dest_key, nex_version);
     let encrypted_session_ticket = Ticket {
         pid: dest_act_login_data.0,
         session_key: internal_data.session_key,
@@ -48,69 +109,115 @@ pub fn generate_ticket(
     encrypted_session_ticket
 }
 
-async fn get_login_data_by_pid(pid: u32) -> Option<(u32, [u8; 16])> {
-    let Ok(mut client) = account::Client::new().await else {
-        return None;
-    };
+fn secure_station_url(secure_server_pid: u32, sock_addr: SocketAddrV4) -> StationUrl{
+    StationUrl::new(Type::PRUDPS)
+        .with(PrincipalID(secure_server_pid))
+        .with(StreamID(1))
+        .with(StreamType(10))
+        .with(NatType(2))
+        .with(Address(*sock_addr.ip()))
+        .with(Port(sock_addr.port()))
+        .with(ConnectionID(1))
+}
 
-    let Ok(passwd) = client.get_nex_password(pid).await else {
-        return None;
-    };
+impl AuthHandler{
+    async fn get_login_data_by_pid(&self, pid: u32) -> Result<(u32, [u8; 16]), ErrorCode> {
+        // the guest account isnt a real account so the account backend doesnt know about it
+        if self.allow_guest_login && pid == GUEST_PID {
+            return Ok(Account::guest().get_login_data());
+        }
 
-    Some((pid, passwd))
-}
+        let passwd = self.accounts.get_nex_password(pid).await
+            .map_err(|e| map_account_error(e, ErrorCode::RendezVous_InvalidPID))?;
 
-fn station_url_from_sock_addr(sock_addr: SocketAddrV4) -> String{
-    format!(
-        "prudps:/PID=2;sid=1;stream=10;type=2;address={};port={};CID=1",
-        sock_addr.ip(), sock_addr.port()
-    )
-}
+        Ok((pid, passwd))
+    }
 
-impl Auth for AuthHandler {
-    async fn login(&self, _name: String) -> Result<(), ErrorCode> {
-        todo!()
+    /// Rejects logins during maintenance as well as banned accounts and addresses.
+    async fn check_ban_status(&self, pid: u32) -> Result<(), ErrorCode> {
+        let ip = self.client_addr.map(|a| *a.regular_socket_addr.ip());
+
+        self.moderation.check_login(pid, ip)?;
+
+        if self.allow_guest_login && pid == GUEST_PID {
+            return Ok(());
+        }
+
+        let status = self.accounts.get_ban_status(pid).await
+            .map_err(|e| map_account_error(e, ErrorCode::RendezVous_InvalidPID))?;
+
+        if status.is_banned() {
+            return Err(ErrorCode::RendezVous_AccountDisabled);
+        }
+
+        Ok(())
     }
 
-    async fn login_ex(
+    /// Resolves the name a client logs in with, this is either the pid as a string or the
+    /// username of the account.
+    async fn resolve_login_name(&self, name: &str) -> Result<u32, ErrorCode>{
+        if let Ok(pid) = name.parse() {
+            return Ok(pid);
+        }
+
+        if self.allow_guest_login && name == GUEST_USERNAME {
+            return Ok(GUEST_PID);
+        }
+
+        ACCOUNT_LOOKUP_CACHE.get_pid(self.accounts.as_ref(), name).await
+    }
+
+    /// Resolves the nnid/pnid token a client logs in with in `LoginWithContext`, this only works
+    /// if the account backend can check tokens.
+    async fn resolve_login_token(&self, token: &str) -> Result<u32, ErrorCode>{
+        match self.accounts.get_pid_by_token(token).await {
+            Ok(pid) => Ok(pid),
+            Err(account_provider::Error::Unsupported(what)) => {
+                error!("the account backend doesnt support {}", what);
+                Err(ErrorCode::Core_NotImplemented)
+            }
+            Err(e) => Err(map_account_error(e, ErrorCode::Authentication_TokenParseError)),
+        }
+    }
+
+    /// Common implementation of `Login` and `LoginEx`
+    async fn login_common(
         &self,
-        name: String,
-        _extra_data: Any,
+        name: &str,
     ) -> Result<(QResult, u32, Vec<u8>, ConnectionData, String), ErrorCode> {
-        let Ok(pid) = name.parse() else {
-            return Err(ErrorCode::Core_InvalidArgument);
-        };
+        let pid = self.resolve_login_name(name).await?;
 
-        let Ok(mut client) = account::Client::new().await else {
-            return Err(ErrorCode::Core_Exception);
-        };
+        self.login_pid(pid, None).await.map_err(|e| match e {
+            // the user gave us a name which doesnt exist and not a pid
+            ErrorCode::RendezVous_InvalidPID => ErrorCode::RendezVous_InvalidUsername,
+            e => e
+        })
+    }
 
-        let Ok(passwd) = client.get_nex_password(pid).await else {
-            return Err(ErrorCode::Core_Exception);
-        };
+    /// Logs in the account once we know who it is, this is shared between every login method.
+    async fn login_pid(
+        &self,
+        pid: u32,
+        nex_version: Option<NexVersion>,
+    ) -> Result<(QResult, u32, Vec<u8>, ConnectionData, String), ErrorCode> {
+        let source_login_data = self.get_login_data_by_pid(pid).await?;
+        self.check_ban_status(pid).await?;
 
-        let source_login_data = (pid, passwd);
-        let destination_login_data = self.destination_server_acct.get_login_data();
+        let destination_login_data = self.destination_server_keys.current_login_data();
 
-        let ticket = generate_ticket(source_login_data, destination_login_data);
+        let ticket = generate_ticket(source_login_data, destination_login_data, nex_version).await;
 
         let result = QResult::success(Core_Unknown);
 
         let mut hasher = DefaultHasher::new();
 
-        hasher.write(name.as_bytes());
-        
-        let Ok(addr) = self.control_server.get_url(hasher.finish()).await else {
+        hasher.write_u32(pid);
+
+        let Ok(node) = self.control_server.get_node(hasher.finish()).await else {
             return Err(ErrorCode::Core_Exception);
         };
 
-        let connection_data = ConnectionData {
-            station_url: station_url_from_sock_addr(addr),
-            special_station_url: "".to_string(),
-            //date_time: KerberosDateTime::new(1,1,1,1,1,1),
-            date_time: KerberosDateTime::now(),
-            special_protocols: Vec::new(),
-        };
+        let connection_data = self.connection_data_config.build(self.destination_server_keys.pid(), node);
 
         Ok((
             result,
@@ -120,49 +227,135 @@ impl Auth for AuthHandler {
             self.build_name.to_string() //format!("{}; Rust NEX Version {} by DJMrTV", self.build_name, env!("CARGO_PKG_VERSION")),
         ))
     }
+}
+
+impl Auth for AuthHandler {
+    async fn login(
+        &self,
+        name: String,
+    ) -> Result<(QResult, u32, Vec<u8>, ConnectionData, String), ErrorCode> {
+        self.login_common(&name).await
+    }
+
+    async fn login_ex(
+        &self,
+        name: String,
+        extra_data: Any,
+    ) -> Result<(QResult, u32, Vec<u8>, ConnectionData, String), ErrorCode> {
+        // nothing needs the extra data yet, but knowing what games send is useful
+        match extra_data.decode_dynamic() {
+            Ok(extra_data) => debug!("login extra data: {:?}", extra_data),
+            Err(e) => error!("unable to read login extra data of type {}: {}", extra_data.name, e),
+        }
+
+        self.login_common(&name).await
+    }
 
     async fn request_ticket(
         &self,
         source_pid: u32,
         destination_pid: u32,
     ) -> Result<(QResult, Vec<u8>), ErrorCode> {
-        let Some(source_login_data) = get_login_data_by_pid(source_pid).await else {
-            return Err(ErrorCode::Core_Exception);
-        };
+        let source_login_data = self.get_login_data_by_pid(source_pid).await?;
+        self.check_ban_status(source_pid).await?;
 
-        let desgination_login_data = if destination_pid == self.destination_server_acct.pid {
-            self.destination_server_acct.get_login_data()
+        let (desgination_login_data, nex_version) = if destination_pid == self.destination_server_keys.pid() {
+            (self.destination_server_keys.current_login_data(), *self.nex_version.read().await)
+        } else if let Some(service) = self.service_accounts.get(destination_pid) {
+            (service.get_login_data(), None)
         } else {
-            let Some(login) = get_login_data_by_pid(destination_pid).await else {
-                return Err(ErrorCode::Core_Exception);
-            };
-            login
+            (self.get_login_data_by_pid(destination_pid).await?, None)
         };
 
         let result = QResult::success(Core_Unknown);
 
-        let ticket = generate_ticket(source_login_data, desgination_login_data);
+        let ticket = generate_ticket(source_login_data, desgination_login_data, nex_version).await;
 
         Ok((result, ticket.into()))
     }
 
-    async fn get_pid(&self, _username: String) -> Result<u32, ErrorCode> {
-        Err(ErrorCode::Core_Exception)
+    async fn get_pid(&self, username: String) -> Result<u32, ErrorCode> {
+        ACCOUNT_LOOKUP_CACHE.get_pid(self.accounts.as_ref(), &username).await
+    }
+
+    async fn get_name(&self, pid: u32) -> Result<String, ErrorCode> {
+        ACCOUNT_LOOKUP_CACHE.get_name(self.accounts.as_ref(), pid).await
     }
 
-    async fn get_name(&self, _pid: u32) -> Result<String, ErrorCode> {
-        Err(ErrorCode::Core_Exception)
+    async fn login_with_context(
+        &self,
+        login_data: Any,
+    ) -> Result<(QResult, u32, Vec<u8>, VersionedConnectionData), ErrorCode> {
+        let auth_info = match login_data.decode::<AuthenticationInfo>() {
+            Ok(v) => v,
+            Err(e @ structures::Error::AnyTypeMismatch { .. }) => {
+                error!("unsupported login data: {}", e);
+                return Err(ErrorCode::Core_InvalidArgument);
+            }
+            Err(_) => return Err(ErrorCode::Authentication_TokenParseError),
+        };
+
+        if !SUPPORTED_NGS_VERSIONS.contains(&auth_info.ngs_version) {
+            error!("unsupported ngs version: {}", auth_info.ngs_version);
+            return Err(ErrorCode::Authentication_UnsupportedVersion);
+        }
+
+        let pid = self.resolve_login_token(&auth_info.token).await?;
+
+        let nex_version = auth_info.nex_version();
+
+        let (result, pid, ticket, connection_data, _) = self.login_pid(pid, Some(nex_version)).await?;
+
+        // tickets requested later on for the secure server should carry the version as well
+        *self.nex_version.write().await = Some(nex_version);
+
+        Ok((
+            result,
+            pid,
+            ticket,
+            VersionedConnectionData {
+                nex_version,
+                connection_data,
+            }
+        ))
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use crate::nex::auth_handler::ConnectionDataConfig;
+    use crate::prudp::station_url::StationUrl;
+    use crate::reggie::EdgeNodeInfo;
     use crate::rmc::structures::connection_data::ConnectionData;
     use crate::rmc::structures::qresult::QResult;
     use crate::rmc::structures::RmcSerialize;
     use crate::rmc::response::RMCResponse;
     use std::io::Cursor;
     
+    #[test]
+    fn edge_nodes_override_special_protocols() {
+        let config = ConnectionDataConfig {
+            special_protocols: vec![115],
+            special_station_url: Some(StationUrl::try_from("prudps:/address=10.0.0.2;port=10002").unwrap()),
+        };
+
+        let node = EdgeNodeInfo::new(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 10001));
+
+        let data = config.build(2, node.clone());
+
+        assert_eq!(data.station_url, "prudps:/PID=2;sid=1;stream=10;type=2;address=10.0.0.1;port=10001;CID=1");
+        assert_eq!(data.special_protocols, vec![115]);
+        assert_eq!(data.special_station_url, "prudps:/address=10.0.0.2;port=10002");
+
+        let data = config.build(2, EdgeNodeInfo {
+            special_protocols: vec![116],
+            ..node
+        });
+
+        assert_eq!(data.special_protocols, vec![116]);
+    }
+
     #[test]
     fn test() {
 
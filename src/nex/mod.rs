@@ -1,5 +1,9 @@
 pub mod account;
+pub mod account_lookup;
+pub mod account_provider;
 pub mod auth_handler;
 pub mod user;
 pub mod remote_console;
-pub mod matchmake;
\ No newline at end of file
+pub mod matchmake;
+pub mod moderation;
+pub mod service_accounts;
\ No newline at end of file
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::task::JoinHandle;
+use crate::rmc::response::ErrorCode;
+use crate::util::watch_file;
+
+/// Something which can be banned.
+///
+/// note: there are no device bans as nothing a client sends us while logging in or connecting
+/// carries a device id we could check, the account backend has to take care of those.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BanTarget{
+    Pid(u32),
+    Ip(Ipv4Addr),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BanInfo{
+    pub reason: Option<String>,
+    /// when the ban runs out, `None` means it's permanent
+    pub expires: Option<DateTime<Utc>>,
+}
+
+impl BanInfo{
+    pub fn is_active(&self) -> bool{
+        self.expires.map_or(true, |expires| expires > Utc::now())
+    }
+}
+
+#[derive(Deserialize)]
+struct BanEntry{
+    pid: Option<u32>,
+    ip: Option<Ipv4Addr>,
+    /// only here so device bans get refused instead of silently doing nothing
+    device: Option<String>,
+    reason: Option<String>,
+    expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct ModerationFile{
+    #[serde(default)]
+    maintenance: bool,
+    #[serde(default)]
+    bans: Vec<BanEntry>,
+}
+
+/// Ban list and maintenance flag which get checked whenever someone logs in or connects.
+///
+/// the moderation file is toml and looks like this, every entry can ban a pid and/or ip (device
+/// bans arent supported, see [`BanTarget`]):
+/// ```toml
+/// maintenance = false
+///
+/// [[bans]]
+/// pid = 1699562917
+/// reason = "cheating"
+/// expires = "2030-01-01T00:00:00Z"
+///
+/// [[bans]]
+/// ip = "203.0.113.7"
+/// ```
+#[derive(Default)]
+pub struct Moderation{
+    bans: RwLock<HashMap<BanTarget, BanInfo>>,
+    maintenance: AtomicBool,
+}
+
+impl Moderation{
+    pub fn new(maintenance: bool) -> Self{
+        Self{
+            bans: Default::default(),
+            maintenance: AtomicBool::new(maintenance),
+        }
+    }
+
+    pub fn ban(&self, target: BanTarget, info: BanInfo){
+        self.bans.write().expect("ban list poisoned").insert(target, info);
+    }
+
+    pub fn unban(&self, target: &BanTarget){
+        self.bans.write().expect("ban list poisoned").remove(target);
+    }
+
+    /// Gets the first active ban which applies to any of the targets.
+    pub fn find_ban(&self, targets: &[BanTarget]) -> Option<BanInfo>{
+        let bans = self.bans.read().expect("ban list poisoned");
+
+        targets.iter()
+            .filter_map(|t| bans.get(t))
+            .find(|b| b.is_active())
+            .cloned()
+    }
+
+    /// Checks if a client with the given pid and address is allowed to connect, this doesnt
+    /// care about maintenance so that sessions which were already running can finish.
+    pub fn is_banned(&self, pid: u32, ip: Option<Ipv4Addr>) -> bool{
+        let mut targets = vec![BanTarget::Pid(pid)];
+
+        targets.extend(ip.map(BanTarget::Ip));
+
+        self.find_ban(&targets).is_some()
+    }
+
+    /// Checks if a new login is allowed, this rejects everyone during maintenance.
+    pub fn check_login(&self, pid: u32, ip: Option<Ipv4Addr>) -> Result<(), ErrorCode>{
+        if self.is_maintenance() {
+            return Err(ErrorCode::RendezVous_GameServerMaintenance);
+        }
+
+        if self.is_banned(pid, ip) {
+            return Err(ErrorCode::RendezVous_AccountDisabled);
+        }
+
+        Ok(())
+    }
+
+    pub fn is_maintenance(&self) -> bool{
+        self.maintenance.load(Ordering::Relaxed)
+    }
+
+    pub fn set_maintenance(&self, maintenance: bool){
+        self.maintenance.store(maintenance, Ordering::Relaxed);
+    }
+
+    /// Replaces the ban list and maintenance flag with the contents of the moderation file.
+    pub fn reload_from_file(&self, path: &Path) -> io::Result<()>{
+        let contents = fs::read_to_string(path)?;
+
+        let file: ModerationFile = toml::from_str(&contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut bans = HashMap::new();
+
+        for entry in file.bans {
+            if let Some(device) = entry.device {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("device bans arent supported, unable to ban {}", device)
+                ));
+            }
+
+            let info = BanInfo{
+                reason: entry.reason,
+                expires: entry.expires,
+            };
+
+            let targets = entry.pid.map(BanTarget::Pid).into_iter()
+                .chain(entry.ip.map(BanTarget::Ip));
+
+            for target in targets {
+                bans.insert(target, info.clone());
+            }
+        }
+
+        *self.bans.write().expect("ban list poisoned") = bans;
+        self.set_maintenance(file.maintenance);
+
+        Ok(())
+    }
+
+    /// Watches the moderation file and reloads it whenever it gets modified, this way bans and
+    /// maintenance apply to every server without restarting them.
+    pub fn spawn_reload_task(this: Arc<Self>, path: PathBuf, interval: Duration) -> JoinHandle<()>{
+        watch_file(path, interval, move |path| this.reload_from_file(path))
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::net::Ipv4Addr;
+    use chrono::{TimeDelta, Utc};
+    use crate::nex::moderation::{BanInfo, BanTarget, Moderation};
+    use crate::rmc::response::ErrorCode;
+
+    #[test]
+    fn bans_and_maintenance(){
+        let moderation = Moderation::new(false);
+        let ip = Ipv4Addr::new(203, 0, 113, 7);
+
+        moderation.ban(BanTarget::Ip(ip), BanInfo{
+            reason: Some("cheating".to_owned()),
+            expires: None,
+        });
+        moderation.ban(BanTarget::Pid(1699562917), BanInfo{
+            reason: None,
+            expires: Some(Utc::now() - TimeDelta::hours(1)),
+        });
+
+        assert!(moderation.is_banned(1699562916, Some(ip)));
+        assert!(!moderation.is_banned(1699562916, Some(Ipv4Addr::LOCALHOST)));
+        // expired bans dont count
+        assert!(!moderation.is_banned(1699562917, None));
+
+        assert_eq!(moderation.check_login(1699562916, Some(ip)), Err(ErrorCode::RendezVous_AccountDisabled));
+
+        moderation.set_maintenance(true);
+
+        assert_eq!(moderation.check_login(1699562916, None), Err(ErrorCode::RendezVous_GameServerMaintenance));
+        // maintenance doesnt kick out people who are already connected
+        assert!(!moderation.is_banned(1699562916, None));
+    }
+
+    #[test]
+    fn device_bans_get_refused(){
+        let moderation = Moderation::new(false);
+
+        let path = std::env::temp_dir().join(format!("rnex_moderation_{}", std::process::id()));
+        std::fs::write(&path, "[[bans]]\npid = 1699562917\n\n[[bans]]\ndevice = \"1234\"\n").unwrap();
+
+        assert!(moderation.reload_from_file(&path).is_err());
+        // nothing of the file gets applied
+        assert!(!moderation.is_banned(1699562917, None));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
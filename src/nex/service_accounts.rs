@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::Deserialize;
+use crate::nex::account::Account;
+
+#[derive(Deserialize)]
+struct ServiceAccountEntry{
+    pid: u32,
+    name: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+struct ServiceAccountFile{
+    #[serde(default)]
+    services: Vec<ServiceAccountEntry>,
+}
+
+/// Quazal accounts of the nex services we can issue tickets for (datastore, ranking, other
+/// secure servers, ...), the secure server this auth server belongs to isnt part of this as its
+/// keys live in a [`ServerKeyring`](crate::kerberos::keyring::ServerKeyring).
+///
+/// the service account file is toml and looks like this:
+/// ```toml
+/// [[services]]
+/// pid = 3
+/// name = "DataStore"
+/// password = "password"
+/// ```
+#[derive(Default)]
+pub struct ServiceAccountRegistry{
+    accounts: HashMap<u32, Account>,
+}
+
+impl ServiceAccountRegistry{
+    pub fn new(accounts: impl IntoIterator<Item = Account>) -> Self{
+        Self{
+            accounts: accounts.into_iter().map(|a| (a.pid, a)).collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self>{
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(contents: &str) -> io::Result<Self>{
+        let file: ServiceAccountFile = toml::from_str(contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let accounts = file.services.iter()
+            .map(|s| Account::try_new(s.pid, &s.name, &s.password))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Self::new(accounts))
+    }
+
+    pub fn get(&self, pid: u32) -> Option<&Account>{
+        self.accounts.get(&pid)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&Account>{
+        self.accounts.values().find(|a| a.username == name)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::nex::account::kerberos_password_from_str;
+    use crate::nex::service_accounts::ServiceAccountRegistry;
+
+    #[test]
+    fn load_service_accounts(){
+        let registry = ServiceAccountRegistry::from_toml(r#"
+            [[services]]
+            pid = 3
+            name = "DataStore"
+            password = "datastore"
+        "#).unwrap();
+
+        assert_eq!(registry.get(3).unwrap().get_login_data(), (3, kerberos_password_from_str("datastore").unwrap()));
+        assert_eq!(registry.get_by_name("DataStore").unwrap().pid, 3);
+        assert!(registry.get(4).is_none());
+    }
+}
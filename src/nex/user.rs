@@ -22,6 +22,9 @@ use crate::rmc::structures::matchmake::{AutoMatchmakeParam, CreateMatchmakeSessi
 
 use crate::rmc::structures::qresult::QResult;
 use macros::rmc_struct;
+use async_trait::async_trait;
+use crate::rmc::interceptor::{IncomingCall, RmcInterceptor};
+use crate::rmc::protocols::method_name;
 use std::sync::{Arc, Weak};
 use log::info;
 use tokio::sync::{Mutex, RwLock};
@@ -43,6 +46,9 @@ define_rmc_proto!(
 #[rmc_struct(UserProtocol)]
 pub struct User {
     pub pid: u32,
+    /// guests can look around but arent allowed to create or join gatherings, the gateway of a
+    /// guest needs [`GuestRestrictions`] for this
+    pub guest: bool,
     pub ip: PRUDPSockAddr,
     pub this: Weak<User>,
     pub remote: RemoteConsole,
@@ -50,6 +56,36 @@ pub struct User {
     pub matchmake_manager: Arc<MatchmakeManager>,
 }
 
+/// The only methods guests may call, everything else gets refused.
+const GUEST_METHODS: &[(u16, &str)] = &[
+    (RawSecureInfo::PROTOCOL_ID, "register"),
+    (RawSecureInfo::PROTOCOL_ID, "replace_url"),
+    (RawMatchmakeExtensionInfo::PROTOCOL_ID, "get_playing_session"),
+    (RawMatchmakeExtensionInfo::PROTOCOL_ID, "find_matchmake_session_by_gathering_id_detail"),
+    (RawMatchmakeInfo::PROTOCOL_ID, "get_session_urls"),
+    (RawNatTraversalInfo::PROTOCOL_ID, "request_probe_initiation"),
+    (RawNatTraversalInfo::PROTOCOL_ID, "request_probe_initialization_ext"),
+    (RawNatTraversalInfo::PROTOCOL_ID, "report_nat_traversal_result"),
+    (RawNatTraversalInfo::PROTOCOL_ID, "report_nat_properties"),
+];
+
+/// Refuses every call of a guest which isnt in [`GUEST_METHODS`].
+pub struct GuestRestrictions;
+
+#[async_trait]
+impl RmcInterceptor for GuestRestrictions{
+    async fn before_call(&self, call: &IncomingCall<'_>) -> Result<(), ErrorCode> {
+        let allowed = method_name(call.protocol_id, call.method_id)
+            .is_some_and(|name| GUEST_METHODS.contains(&(call.protocol_id, name)));
+
+        if !allowed {
+            return Err(ErrorCode::RendezVous_PermissionDenied);
+        }
+
+        Ok(())
+    }
+}
+
 impl Secure for User {
     async fn register(
         &self,
@@ -565,4 +601,90 @@ impl NatTraversal for User {
 
 impl Ranking for User{
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+pub(crate) mod test{
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicU32;
+    use crate::nex::matchmake::MatchmakeManager;
+    use crate::nex::remote_console::RemoteConsole;
+    use crate::nex::user::{GuestRestrictions, RemoteUserProtocol, User};
+    use crate::prudp::packet::VirtualPort;
+    use crate::prudp::sockaddr::PRUDPSockAddr;
+    use crate::rmc::message::RMCMessage;
+    use crate::rmc::protocols::matchmake::RemoteMatchmake;
+    use crate::rmc::protocols::matchmake_extension::RemoteMatchmakeExtension;
+    use crate::rmc::protocols::{new_local_rmc_pair, HasRmcConnection, OnlyRemote, RemoteCallError, RemoteInstantiatable};
+    use crate::rmc::response::ErrorCode;
+    use crate::rmc::interceptor::{IncomingCall, RmcInterceptor};
+
+    pub fn matchmake_manager() -> Arc<MatchmakeManager>{
+        Arc::new(MatchmakeManager{
+            gid_counter: AtomicU32::new(1),
+            sessions: Default::default(),
+            users: Default::default(),
+            rv_cid_counter: AtomicU32::new(1),
+        })
+    }
+
+    /// Spins up a user on an in-memory connection, the returned remote is what the client sees.
+    pub fn spawn_user(mmm: Arc<MatchmakeManager>, pid: u32) -> (Arc<User>, Arc<OnlyRemote<RemoteUserProtocol>>){
+        new_local_rmc_pair(|r| Arc::new_cyclic(|this| User{
+            this: this.clone(),
+            ip: PRUDPSockAddr::new(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 60000), VirtualPort::new(1, 10)),
+            pid,
+            guest: false,
+            remote: RemoteConsole::new(r),
+            matchmake_manager: mmm,
+            station_url: Default::default(),
+        }))
+    }
+
+    #[tokio::test]
+    async fn user_over_rmc(){
+        let (_user, remote) = spawn_user(matchmake_manager(), 1699562916);
+
+        assert_eq!(remote.unregister_gathering(1).await, Ok(true));
+        assert!(remote.get_playing_session(vec![1699562916]).await.unwrap().is_empty());
+
+        let unknown_call = RMCMessage{
+            protocol_id: 0x7FF,
+            method_id: 1,
+            call_id: remote.get_connection().next_call_id(),
+            rest_of_data: Vec::new(),
+        };
+
+        assert!(matches!(
+            remote.get_connection().make_raw_call::<()>(&unknown_call).await,
+            Err(RemoteCallError::ServerError(ErrorCode::Core_NotImplemented))
+        ));
+    }
+
+    fn guest_call(protocol_id: u16, method_id: u32) -> IncomingCall<'static>{
+        IncomingCall{
+            protocol_id,
+            method_id,
+            call_id: 1,
+            caller: None,
+            parameters: &[],
+        }
+    }
+
+    #[tokio::test]
+    async fn guest_restrictions(){
+        // get_playing_session and find_matchmake_session_by_gathering_id_detail
+        assert_eq!(GuestRestrictions.before_call(&guest_call(109, 16)).await, Ok(()));
+        assert_eq!(GuestRestrictions.before_call(&guest_call(109, 41)).await, Ok(()));
+        // register
+        assert_eq!(GuestRestrictions.before_call(&guest_call(11, 1)).await, Ok(()));
+
+        // creating, joining and changing gatherings
+        for method_id in [1, 2, 8, 34, 38, 39, 40]{
+            assert_eq!(GuestRestrictions.before_call(&guest_call(109, method_id)).await, Err(ErrorCode::RendezVous_PermissionDenied));
+        }
+        assert_eq!(GuestRestrictions.before_call(&guest_call(21, 42)).await, Err(ErrorCode::RendezVous_PermissionDenied));
+        assert_eq!(GuestRestrictions.before_call(&guest_call(50, 1)).await, Err(ErrorCode::RendezVous_PermissionDenied));
+        assert_eq!(GuestRestrictions.before_call(&guest_call(0x7FF, 1)).await, Err(ErrorCode::RendezVous_PermissionDenied));
+    }
+}
@@ -294,8 +294,6 @@ impl PRUDPV1Packet {
         let packet_signature: [u8; 16] = reader.read_struct(IS_BIG_ENDIAN)?;
         //let packet_signature: [u8; 16] = [0; 16];
 
-        assert_eq!(reader.stream_position().ok(), Some(14 + 16));
-
 
 
         let mut packet_specific_buffer = vec![0u8; header.packet_specific_size as usize];
@@ -481,7 +479,8 @@ impl PRUDPV1Packet {
 mod test {
     use crate::prudp::packet::flags::{NEED_ACK, RELIABLE};
     use crate::prudp::packet::types::DATA;
-    use super::{OptionId, PacketOption, PRUDPV1Header, TypesFlags, VirtualPort};
+    use std::io::Cursor;
+    use super::{OptionId, PacketOption, PRUDPV1Header, PRUDPV1Packet, TypesFlags, VirtualPort};
     #[test]
     fn size_test() {
         assert_eq!(size_of::<PRUDPV1Header>(), 14);
@@ -531,6 +530,26 @@ mod test {
         let header_data: [u8; 8] = bytes.try_into().unwrap();
     }
 
+    #[test]
+    fn consecutive_packets(){
+        let mut first = PRUDPV1Packet::default();
+        first.payload = vec![1, 2, 3];
+        first.set_sizes();
+
+        let mut second = PRUDPV1Packet::default();
+        second.header.sequence_id = 1;
+        second.set_sizes();
+
+        let mut data = Vec::new();
+        first.write_to(&mut data).unwrap();
+        second.write_to(&mut data).unwrap();
+
+        let mut cursor = Cursor::new(&data[..]);
+
+        assert_eq!(PRUDPV1Packet::new(&mut cursor).unwrap(), first);
+        assert_eq!(PRUDPV1Packet::new(&mut cursor).unwrap(), second);
+    }
+
     #[test]
     fn test_types_flags(){
         let types = TypesFlags::default().types(DATA).flags(NEED_ACK | RELIABLE);
@@ -1,4 +1,5 @@
 use std::io::Cursor;
+use chrono::TimeDelta;
 use hmac::digest::consts::U32;
 use log::error;
 use rc4::cipher::StreamCipherCoreWrapper;
@@ -6,47 +7,62 @@ use rc4::{KeyInit, Rc4, Rc4Core, StreamCipher};
 use rc4::consts::U16;
 use typenum::U5;
 use crate::endianness::{IS_BIG_ENDIAN, ReadExtensions};
-use crate::kerberos::{derive_key, TicketInternalData};
-use crate::nex::account::Account;
+use std::sync::Arc;
+use crate::kerberos::{verify_hmac, TicketInternalData};
+use crate::kerberos::keyring::ServerKeyring;
+use crate::nex::moderation::Moderation;
 use crate::prudp::packet::PRUDPV1Packet;
+use crate::prudp::sockaddr::PRUDPSockAddr;
 use crate::prudp::socket::{CryptoHandler, CryptoHandlerConnectionInstance, EncryptionPair};
 use crate::rmc::structures::RmcSerialize;
+use crate::versions::NexVersion;
 
-pub fn read_secure_connection_data(data: &[u8], act: &Account) -> Option<([u8; 32], u32, u32)>{
+/// Reads the connection data sent by the client on connect, the ticket is accepted if it was
+/// signed by any of the given `server_keys`. Returns the session key, pid, check value and the nex
+/// version of the client if the auth server knew it.
+pub fn read_secure_connection_data(data: &[u8], server_keys: &[[u8; 16]]) -> Option<([u8; 32], u32, u32, Option<NexVersion>)>{
     let mut cursor = Cursor::new(data);
 
     let mut ticket_data: Vec<u8> = Vec::deserialize(&mut cursor).ok()?;
     let mut request_data: Vec<u8> = Vec::deserialize(&mut cursor).ok()?;
 
+    let Some(server_key) = server_keys.iter().find(|k| verify_hmac(**k, &ticket_data)) else {
+        error!("ticket isnt signed by any of the accepted server keys");
+        return None;
+    };
+
     let ticket_data_size = ticket_data.len();
 
     let ticket_data = &mut ticket_data[0..ticket_data_size-0x10];
 
-    let server_key = derive_key(act.pid, act.kerbros_password);
-
     let mut rc4: StreamCipherCoreWrapper<Rc4Core<U16>> =
-        Rc4::new_from_slice(&server_key).expect("unable to init rc4 keystream");
+        Rc4::new_from_slice(server_key).expect("unable to init rc4 keystream");
 
     rc4.apply_keystream(ticket_data);
 
-    let ticket_data: &TicketInternalData = match bytemuck::try_from_bytes(ticket_data){
-        Ok(v) => v,
-        Err(e) => {
-            error!("unable to read internal ticket data: {}", e);
-            return None;
-        }
+    let Some((ticket_data, nex_version)) = TicketInternalData::read(ticket_data) else {
+        error!("unable to read internal ticket data of size {}", ticket_data.len());
+        return None;
     };
 
-    // todo: add ticket expiration
-
     let TicketInternalData{
         session_key,
         pid: ticket_source_pid,
         issued_time
-    } = *ticket_data;
+    } = ticket_data;
+
+    if issued_time.has_expired(TICKET_LIFETIME){
+        error!("ticket of {} has expired or has an invalid issue time: {}", ticket_source_pid, issued_time);
+        return None;
+    }
 
-    // todo: add checking if tickets are signed with a valid md5-hmac
     let request_data_length = request_data.len();
+
+    if request_data_length < 0x10{
+        error!("request data of {} is too small", ticket_source_pid);
+        return None;
+    }
+
     let request_data = &mut request_data[0.. request_data_length - 0x10];
 
     let mut rc4: StreamCipherCoreWrapper<Rc4Core<U32>> =
@@ -59,9 +75,7 @@ pub fn read_secure_connection_data(data: &[u8], act: &Account) -> Option<([u8; 3
     let pid: u32 = reqest_data_cursor.read_struct(IS_BIG_ENDIAN).ok()?;
 
     if pid != ticket_source_pid{
-        let ticket_created_on = issued_time.to_regular_time();
-
-        error!("someone tried to spoof their pid, ticket was created on: {}", ticket_created_on.to_rfc2822());
+        error!("someone tried to spoof their pid, ticket was created on: {}", issued_time);
         return None;
     }
 
@@ -70,9 +84,12 @@ pub fn read_secure_connection_data(data: &[u8], act: &Account) -> Option<([u8; 3
 
 
 
-    Some((session_key, pid, response_check))
+    Some((session_key, pid, response_check, nex_version))
 }
 
+/// How long a ticket stays valid after it has been issued by the auth server.
+pub const TICKET_LIFETIME: TimeDelta = TimeDelta::days(1);
+
 type Rc4U32 = StreamCipherCoreWrapper<Rc4Core<U32>>;
 
 pub fn generate_secure_encryption_pairs(mut session_key: [u8; 32], count: u8) -> Vec<EncryptionPair<Rc4<U32>>>{
@@ -102,7 +119,24 @@ pub fn generate_secure_encryption_pairs(mut session_key: [u8; 32], count: u8) ->
 }
 
 
-pub struct Secure(pub &'static str, pub Account);
+pub struct Secure{
+    access_key: &'static str,
+    server_keys: Arc<ServerKeyring>,
+    moderation: Arc<Moderation>,
+}
+
+impl Secure{
+    /// Creates the secure crypto handler, tickets signed with any key of `server_keys` get
+    /// accepted. The keys are already derived in the keyring so connecting clients dont have to
+    /// wait on it. Banned clients get refused when connecting.
+    pub fn new(access_key: &'static str, server_keys: Arc<ServerKeyring>, moderation: Arc<Moderation>) -> Self{
+        Self{
+            access_key,
+            server_keys,
+            moderation,
+        }
+    }
+}
 
 
 pub struct SecureInstance {
@@ -112,6 +146,7 @@ pub struct SecureInstance {
     self_signature: [u8; 16],
     remote_signature: [u8; 16],
     pid: u32,
+    nex_version: Option<NexVersion>,
 }
 
 impl CryptoHandler for Secure {
@@ -119,14 +154,20 @@ impl CryptoHandler for Secure {
 
     fn instantiate(
         &self,
+        remote_addr: PRUDPSockAddr,
         remote_signature: [u8; 16],
         self_signature: [u8; 16],
         payload: &[u8],
         substream_count: u8,
     ) -> Option<(Vec<u8>, Self::CryptoConnectionInstance)> {
-        let (session_key, pid, check_value) = read_secure_connection_data(payload, &self.1)?;
+        let (session_key, pid, check_value, nex_version) = read_secure_connection_data(payload, &self.server_keys.keys())?;
 
-        let check_value_response = check_value + 1;
+        if self.moderation.is_banned(pid, Some(*remote_addr.regular_socket_addr.ip())) {
+            error!("refused connection from banned user {} at {:?}", pid, remote_addr);
+            return None;
+        }
+
+        let check_value_response = check_value.wrapping_add(1);
 
         let data = bytemuck::bytes_of(&check_value_response);
 
@@ -140,9 +181,10 @@ impl CryptoHandler for Secure {
             response,
             SecureInstance {
                 pid,
+                nex_version,
                 streams: encryption_pairs,
                 session_key,
-                access_key: self.0,
+                access_key: self.access_key,
                 remote_signature,
                 self_signature,
             },
@@ -151,7 +193,7 @@ impl CryptoHandler for Secure {
 
     fn sign_pre_handshake(&self, packet: &mut PRUDPV1Packet) {
         packet.set_sizes();
-        packet.calculate_and_assign_signature(self.0, None, None);
+        packet.calculate_and_assign_signature(self.access_key, None, None);
     }
 }
 
@@ -175,6 +217,10 @@ impl CryptoHandlerConnectionInstance for SecureInstance {
         self.pid
     }
 
+    fn get_nex_version(&self) -> Option<NexVersion> {
+        self.nex_version
+    }
+
     fn sign_connect(&self, packet: &mut PRUDPV1Packet) {
         packet.set_sizes();
         packet.calculate_and_assign_signature(self.access_key, None, Some(self.self_signature));
@@ -5,6 +5,7 @@ use crate::prudp::packet::PacketOption::{
 };
 use crate::prudp::packet::{PRUDPV1Header, PRUDPV1Packet, TypesFlags, VirtualPort};
 use crate::prudp::sockaddr::PRUDPSockAddr;
+use crate::versions::NexVersion;
 use async_trait::async_trait;
 use log::info;
 use log::error;
@@ -40,6 +41,8 @@ impl<T: StreamCipher + Send> EncryptionPair<T> {
 
 pub struct CommonConnection {
     pub user_id: u32,
+    /// nex version of the client if the crypto handler knows it
+    pub nex_version: Option<NexVersion>,
     pub socket_addr: PRUDPSockAddr,
     pub server_port: VirtualPort,
     session_id: u8,
@@ -350,6 +353,7 @@ impl<T: CryptoHandler> InternalSocket<T> {
     ) {
         let common = Arc::new(CommonConnection {
             user_id: crypto_handler_instance.get_user_id(),
+            nex_version: crypto_handler_instance.get_nex_version(),
             socket_addr,
             session_id,
             server_port: self.virtual_port,
@@ -419,6 +423,7 @@ impl<T: CryptoHandler> InternalSocket<T> {
         let session_id = packet.header.session_id;
 
         let Some((return_data, crypto)) = self.crypto_handler.instantiate(
+            address,
             remote_signature,
             *own_signature,
             &packet.payload,
@@ -685,7 +690,7 @@ impl<T: CryptoHandler> AnyInternalSocket for InternalSocket<T> {
 
         let (_, crypt) =
             self.crypto_handler
-                .instantiate(remote_signature, *own_signature, &[], 1)?;
+                .instantiate(address, remote_signature, *own_signature, &[], 1)?;
 
         //todo: make this work for secure servers as well
         self.create_connection(crypt, address, 0, true).await;
@@ -733,6 +738,9 @@ pub trait CryptoHandlerConnectionInstance: Send + Sync + 'static {
     fn encrypt_outgoing(&mut self, substream: u8, data: &mut [u8]);
 
     fn get_user_id(&self) -> u32;
+    fn get_nex_version(&self) -> Option<NexVersion> {
+        None
+    }
     fn sign_connect(&self, packet: &mut PRUDPV1Packet);
     fn sign_packet(&self, packet: &mut PRUDPV1Packet);
     fn verify_packet(&self, packet: &PRUDPV1Packet) -> bool;
@@ -743,6 +751,7 @@ pub trait CryptoHandler: Send + Sync + 'static {
 
     fn instantiate(
         &self,
+        remote_addr: PRUDPSockAddr,
         remote_signature: [u8; 16],
         own_signature: [u8; 16],
         _: &[u8],
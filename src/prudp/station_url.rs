@@ -44,6 +44,19 @@ pub struct StationUrl{
 }
 
 impl StationUrl{
+    /// Creates a station url without any options, use [`StationUrl::with`] to add them.
+    pub fn new(url_type: Type) -> Self{
+        Self{
+            url_type,
+            options: Vec::new()
+        }
+    }
+
+    pub fn with(mut self, option: UrlOptions) -> Self{
+        self.options.push(option);
+        self
+    }
+
     pub fn read_options(options: &str) -> Option<Vec<UrlOptions>>{
         let mut options_out = Vec::new();
 
@@ -78,6 +91,9 @@ impl StationUrl{
                 "stream" => {
                     options_out.push(StreamType(option_value.parse().ok()?))
                 }
+                "cid" => {
+                    options_out.push(ConnectionID(option_value.parse().ok()?))
+                }
                 "RVCID" => {
                     options_out.push(RVConnectionID(option_value.parse().ok()?))
                 }
@@ -163,7 +179,12 @@ impl<'a> Into<String> for &'a StationUrl{
             write!(url, ";").expect("failed to write");
         }
 
-        url[0..url.len()-1].into()
+        // drop the `;` after the last option, without options there is nothing to drop
+        if !self.options.is_empty(){
+            url.pop();
+        }
+
+        url
     }
 }
 
@@ -195,4 +216,43 @@ impl Debug for StationUrl{
         let str: String = self.into();
         f.write_str(&str)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test{
+    use std::net::Ipv4Addr;
+    use crate::prudp::station_url::{StationUrl, Type};
+    use crate::prudp::station_url::UrlOptions::{Address, ConnectionID, NatType, Port, PrincipalID, StreamID, StreamType};
+    use crate::rmc::structures::RmcSerialize;
+
+    #[test]
+    fn builder_round_trip(){
+        let url = StationUrl::new(Type::PRUDPS)
+            .with(PrincipalID(2))
+            .with(StreamID(1))
+            .with(StreamType(10))
+            .with(NatType(2))
+            .with(Address(Ipv4Addr::new(10, 0, 0, 1)))
+            .with(Port(10001))
+            .with(ConnectionID(1));
+
+        let str = url.to_string();
+
+        assert_eq!(str, "prudps:/PID=2;sid=1;stream=10;type=2;address=10.0.0.1;port=10001;CID=1");
+        assert_eq!(StationUrl::try_from(str.as_str()).unwrap().options.len(), url.options.len());
+
+        let url = StationUrl::new(Type::PRUDPS);
+
+        assert_eq!(url.to_string(), "prudps:/");
+        assert!(StationUrl::try_from(url.to_string().as_str()).unwrap().options.is_empty());
+    }
+
+    #[test]
+    fn url_list(){
+        let urls = vec![
+            StationUrl::try_from("prudps:/address=10.0.0.1;port=10001").unwrap(),
+            StationUrl::try_from("prudp:/address=10.0.0.2;port=10002").unwrap(),
+        ];
+        assert_eq!(Vec::<StationUrl>::deserialize(&mut &urls.to_data()[..]).unwrap(), urls);
+    }
+}
@@ -2,6 +2,7 @@ use once_cell::sync::Lazy;
 use rc4::{Key, KeyInit, Rc4, StreamCipher};
 use typenum::U5;
 use crate::prudp::packet::PRUDPV1Packet;
+use crate::prudp::sockaddr::PRUDPSockAddr;
 use crate::prudp::socket::{CryptoHandler, CryptoHandlerConnectionInstance, EncryptionPair};
 
 pub struct Unsecure(pub &'static str);
@@ -25,6 +26,7 @@ impl CryptoHandler for Unsecure {
 
     fn instantiate(
         &self,
+        _: PRUDPSockAddr,
         remote_signature: [u8; 16],
         self_signature: [u8; 16],
         _: &[u8],
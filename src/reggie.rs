@@ -62,10 +62,34 @@ impl<T: AsyncWrite + Unpin> UnitPacketWrite for T{}
 
 
 
+/// What an edge node tells the edge node holder about itself when registering.
+#[derive(RmcSerialize, Debug, Clone)]
+#[rmc_struct(0)]
+pub struct EdgeNodeInfo{
+    pub address: SocketAddrV4,
+    /// special protocols the clients connecting to this node should use, overrides the ones
+    /// configured on the auth server when not empty
+    pub special_protocols: Vec<u8>,
+    /// overrides the special station url configured on the auth server when not empty
+    pub special_station_url: String,
+}
+
+impl EdgeNodeInfo{
+    pub fn new(address: SocketAddrV4) -> Self{
+        Self{
+            address,
+            special_protocols: Vec::new(),
+            special_station_url: String::new(),
+        }
+    }
+}
+
 #[rmc_proto(1)]
 pub trait EdgeNodeManagement {
     #[method_id(1)]
     async fn get_url(&self, seed: u64) -> Result<SocketAddrV4, ErrorCode>;
+    #[method_id(2)]
+    async fn get_node(&self, seed: u64) -> Result<EdgeNodeInfo, ErrorCode>;
 }
 
 define_rmc_proto!(
@@ -78,5 +102,6 @@ define_rmc_proto!(
 #[repr(u32)]
 pub enum EdgeNodeHolderConnectOption{
     DontRegister = 0,
-    Register(SocketAddrV4) = 1
+    Register(SocketAddrV4) = 1,
+    RegisterWithInfo(EdgeNodeInfo) = 2
 }
@@ -0,0 +1,69 @@
+//! Per connection settings which (de)serialization needs but which cant be passed through
+//! [`crate::rmc::structures::RmcSerialize`], the rmc gateway sets these for every call it handles.
+//!
+//! The context lives in a task local so it does not carry over into tasks started with
+//! [`tokio::spawn`] or [`tokio::task::spawn_blocking`], code which (de)serializes there has to
+//! take it along with [`ConnectionContext::current`] and [`ConnectionContext::scope`].
+
+use std::future::Future;
+use crate::rmc::structures::limits::ReadLimits;
+use crate::versions::NexVersion;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionContext{
+    /// the nex version of the other side, `None` if it is the default version of the game
+    pub nex_version: Option<NexVersion>,
+    /// limits for everything the other side sends us
+    pub read_limits: ReadLimits,
+}
+
+tokio::task_local! {
+    static CONNECTION_CONTEXT: ConnectionContext;
+}
+
+impl ConnectionContext{
+    /// Gets the context of the connection which is currently being handled or the defaults
+    /// otherwise.
+    pub fn current() -> Self{
+        CONNECTION_CONTEXT.try_with(|c| *c).unwrap_or_default()
+    }
+
+    /// Runs `fut` with this as the [`ConnectionContext::current`] context.
+    pub async fn scope<F: Future>(self, fut: F) -> F::Output{
+        CONNECTION_CONTEXT.scope(self, fut).await
+    }
+
+    /// Same as [`ConnectionContext::scope`] but for code which doesnt await anything.
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R{
+        CONNECTION_CONTEXT.sync_scope(self, f)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::rmc::connection_context::ConnectionContext;
+    use crate::rmc::structures::limits::{read_limits, ReadLimits};
+    use crate::versions::{current_nex_version, default_nex_version, NexVersion};
+
+    #[tokio::test]
+    async fn carried_into_spawned_tasks(){
+        let context = ConnectionContext{
+            nex_version: Some(NexVersion::new(4, 0, 0)),
+            read_limits: ReadLimits{ max_size: 16, ..Default::default() },
+        };
+
+        let (outside, inside) = context.scope(async {
+            let outside = tokio::spawn(async { (current_nex_version(), read_limits()) });
+
+            let context = ConnectionContext::current();
+            let inside = tokio::task::spawn_blocking(move || {
+                context.sync_scope(|| (current_nex_version(), read_limits()))
+            });
+
+            (outside.await.unwrap(), inside.await.unwrap())
+        }).await;
+
+        assert_eq!(outside, (default_nex_version(), ReadLimits::default()));
+        assert_eq!(inside, (NexVersion::new(4, 0, 0), ReadLimits{ max_size: 16, ..Default::default() }));
+    }
+}
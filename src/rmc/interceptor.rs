@@ -0,0 +1,129 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use crate::prudp::sockaddr::PRUDPSockAddr;
+use crate::rmc::response::ErrorCode;
+
+/// Who is on the other side of an rmc gateway, this is only known for client connections.
+#[derive(Debug, Copy, Clone)]
+pub struct CallerIdentity{
+    pub pid: u32,
+    pub address: PRUDPSockAddr,
+}
+
+/// An incoming call as seen by [`RmcInterceptor`]s, the parameters are still serialized.
+pub struct IncomingCall<'a>{
+    pub protocol_id: u16,
+    pub method_id: u32,
+    pub call_id: u32,
+    pub caller: Option<&'a CallerIdentity>,
+    pub parameters: &'a [u8],
+}
+
+/// Wraps every incoming call of a gateway, this is where things like logging, permission checks,
+/// metrics or rate limits go.
+#[async_trait]
+pub trait RmcInterceptor: Send + Sync{
+    /// Gets called before the call gets handled, returning an error answers the call with that
+    /// error instead of handling it (calls to `NoReturn` protocols just get dropped).
+    async fn before_call(&self, call: &IncomingCall<'_>) -> Result<(), ErrorCode>;
+
+    /// Gets called after the call was handled (not if it got rejected), the parameters got moved
+    /// into the call by then so they are always empty here.
+    async fn after_call(&self, _call: &IncomingCall<'_>, _elapsed: Duration){}
+}
+
+/// The interceptors of a gateway, they run in the order they were added in and `after_call`
+/// runs in reverse.
+#[derive(Clone, Default)]
+pub struct InterceptorChain(Vec<Arc<dyn RmcInterceptor>>);
+
+impl InterceptorChain{
+    pub fn new() -> Self{
+        Self::default()
+    }
+
+    pub fn with(mut self, interceptor: impl RmcInterceptor + 'static) -> Self{
+        self.0.push(Arc::new(interceptor));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.0.is_empty()
+    }
+
+    /// Runs every interceptor until one of them rejects the call.
+    pub async fn before_call(&self, call: &IncomingCall<'_>) -> Result<(), ErrorCode>{
+        for interceptor in &self.0 {
+            interceptor.before_call(call).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn after_call(&self, call: &IncomingCall<'_>, elapsed: Duration){
+        for interceptor in self.0.iter().rev() {
+            interceptor.after_call(call, elapsed).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use crate::rmc::interceptor::{IncomingCall, InterceptorChain, RmcInterceptor};
+    use crate::rmc::response::ErrorCode;
+
+    struct Count(Arc<AtomicU32>);
+
+    #[async_trait]
+    impl RmcInterceptor for Count{
+        async fn before_call(&self, _call: &IncomingCall<'_>) -> Result<(), ErrorCode> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    struct DenyProtocol(u16);
+
+    #[async_trait]
+    impl RmcInterceptor for DenyProtocol{
+        async fn before_call(&self, call: &IncomingCall<'_>) -> Result<(), ErrorCode> {
+            if call.protocol_id == self.0 {
+                return Err(ErrorCode::Core_AccessDenied);
+            }
+
+            Ok(())
+        }
+    }
+
+    fn call(protocol_id: u16) -> IncomingCall<'static>{
+        IncomingCall{
+            protocol_id,
+            method_id: 1,
+            call_id: 1,
+            caller: None,
+            parameters: &[],
+        }
+    }
+
+    #[tokio::test]
+    async fn interceptors_can_reject_calls(){
+        let before = Arc::new(AtomicU32::new(0));
+        let after = Arc::new(AtomicU32::new(0));
+
+        let chain = InterceptorChain::new()
+            .with(Count(before.clone()))
+            .with(DenyProtocol(109))
+            .with(Count(after.clone()));
+
+        assert_eq!(chain.before_call(&call(10)).await, Ok(()));
+        assert_eq!(chain.before_call(&call(109)).await, Err(ErrorCode::Core_AccessDenied));
+
+        assert_eq!(before.load(Ordering::Relaxed), 2);
+        // the rejected call never reaches the interceptors after the one which rejected it
+        assert_eq!(after.load(Ordering::Relaxed), 1);
+    }
+}
@@ -4,6 +4,26 @@ use bytemuck::bytes_of;
 use log::error;
 use crate::endianness::{IS_BIG_ENDIAN, ReadExtensions};
 use crate::rmc::response::{ErrorCode, RMCResponseResult};
+use crate::rmc::structures::limits::check_size;
+
+/// Protocol ids from this value onwards dont fit into the single protocol id byte and are sent as
+/// this escape value followed by the actual id as a u16.
+pub const EXTENDED_PROTOCOL_ID: u8 = 0x7F;
+
+/// Writes the protocol id of a message or response, `flag` gets ored onto the first byte.
+pub(crate) fn write_protocol_id(output: &mut Vec<u8>, protocol_id: u16, flag: u8){
+    if protocol_id < EXTENDED_PROTOCOL_ID as u16 {
+        output.push(protocol_id as u8 | flag);
+    } else {
+        output.push(EXTENDED_PROTOCOL_ID | flag);
+        output.extend_from_slice(bytes_of(&protocol_id));
+    }
+}
+
+/// Size of the protocol id when written with [`write_protocol_id`].
+pub(crate) fn protocol_id_size(protocol_id: u16) -> usize{
+    if protocol_id < EXTENDED_PROTOCOL_ID as u16 { 1 } else { 3 }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RMCMessage{
@@ -18,13 +38,16 @@ impl RMCMessage{
     pub fn new(stream: &mut (impl Seek + Read)) -> io::Result<Self>{
         let size: u32 = stream.read_struct(IS_BIG_ENDIAN)?;
 
+        check_size("rmc message size", size as usize)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
         let mut header_size = 1 + 4 + 4;
 
         let protocol_id: u8 = stream.read_struct(IS_BIG_ENDIAN)?;
         let protocol_id= protocol_id & (!0x80);
 
         let protocol_id: u16 = match protocol_id{
-            0x7F => {
+            EXTENDED_PROTOCOL_ID => {
                 header_size += 2;
                 stream.read_struct(IS_BIG_ENDIAN)?
             },
@@ -54,15 +77,13 @@ impl RMCMessage{
     }
 
     pub fn to_data(&self) -> Vec<u8>{
-        let size = (1 + 4 + 4 + self.rest_of_data.len()) as u32;
+        let size = (protocol_id_size(self.protocol_id) + 4 + 4 + self.rest_of_data.len()) as u32;
 
         let mut output = Vec::new();
 
         output.write_all(bytes_of(&size)).expect("unable to write size");
 
-        let proto_id = self.protocol_id as u8 | 0x80;
-
-        output.write_all(bytes_of(&proto_id)).expect("unable to write size");
+        write_protocol_id(&mut output, self.protocol_id, 0x80);
 
         output.write_all(bytes_of(&self.call_id)).expect("unable to write size");
         output.write_all(bytes_of(&self.method_id)).expect("unable to write size");
@@ -86,4 +107,26 @@ impl RMCMessage{
             data
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test{
+    use std::io::Cursor;
+    use crate::rmc::message::RMCMessage;
+
+    #[test]
+    fn extended_protocol_ids_round_trip(){
+        for protocol_id in [1, 0x7E, 0x7F, 200, 0x1234] {
+            let message = RMCMessage{
+                protocol_id,
+                call_id: 5,
+                method_id: 7,
+                rest_of_data: vec![1, 2, 3]
+            };
+
+            let data = message.to_data();
+
+            assert_eq!(RMCMessage::new(&mut Cursor::new(data)).unwrap(), message);
+        }
+    }
+}
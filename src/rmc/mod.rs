@@ -2,6 +2,9 @@ pub mod message;
 pub mod structures;
 pub mod response;
 pub mod protocols;
+pub mod interceptor;
+pub mod trace;
+pub mod connection_context;
 
 
 
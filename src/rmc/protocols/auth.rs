@@ -1,6 +1,6 @@
 use crate::rmc::response::ErrorCode;
 use crate::rmc::structures::any::Any;
-use crate::rmc::structures::connection_data::ConnectionData;
+use crate::rmc::structures::connection_data::{ConnectionData, VersionedConnectionData};
 use crate::rmc::structures::qresult::QResult;
 use macros::{method_id, rmc_proto};
 
@@ -12,7 +12,10 @@ pub trait Auth {
     /// representation of the `Login` method(for details see the
     /// [kinnay wiki entry](https://github.com/kinnay/NintendoClients/wiki/Authentication-Protocol))
     #[method_id(1)]
-    async fn login(&self, name: String) -> Result<(), ErrorCode>;
+    async fn login(
+        &self,
+        name: String,
+    ) -> Result<(QResult, u32, Vec<u8>, ConnectionData, String), ErrorCode>;
 
     /// representation of the `LoginEx` method(for details see the
     /// [kinnay wiki entry](https://github.com/kinnay/NintendoClients/wiki/Authentication-Protocol))
@@ -37,11 +40,19 @@ pub trait Auth {
     #[method_id(4)]
     async fn get_pid(&self, username: String) -> Result<u32, ErrorCode>;
 
-    /// representation of the `LoginWithContext` method(for details see the
+    /// representation of the `GetName` method(for details see the
     /// [kinnay wiki entry](https://github.com/kinnay/NintendoClients/wiki/Authentication-Protocol))
     #[method_id(5)]
     async fn get_name(&self, pid: u32) -> Result<String, ErrorCode>;
 
-    // `LoginWithContext` is left out here because we don't need it right now and versioning still
-    // needs to be figured out
+    /// representation of the `LoginWithContext` method(for details see the
+    /// [kinnay wiki entry](https://github.com/kinnay/NintendoClients/wiki/Authentication-Protocol))
+    ///
+    /// `login_data` is expected to hold an `AuthenticationInfo`, the returned connection data is
+    /// encoded for the nex version given in there.
+    #[method_id(6)]
+    async fn login_with_context(
+        &self,
+        login_data: Any,
+    ) -> Result<(QResult, u32, Vec<u8>, VersionedConnectionData), ErrorCode>;
 }
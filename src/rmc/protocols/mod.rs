@@ -10,22 +10,29 @@ pub mod matchmake_ext;
 pub mod ranking;
 
 use crate::util::{SendingBufferConnection, SplittableBufferConnection};
+use crate::rmc::interceptor::{CallerIdentity, IncomingCall, InterceptorChain};
 use crate::rmc::message::RMCMessage;
 use crate::rmc::protocols::RemoteCallError::ConnectionBroke;
-use crate::rmc::response::{ErrorCode, RMCResponse, RMCResponseResult};
+use crate::rmc::response::{send_result, ErrorCode, RMCResponse, RMCResponseResult};
 use crate::rmc::structures;
 use crate::rmc::structures::RmcSerialize;
-use log::{error, info};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::future::Future;
 use std::io::Cursor;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::{Mutex, Notify};
-use tokio::time::{sleep, sleep_until, Instant};
+use std::sync::atomic::{AtomicU32, Ordering};
+use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::{sleep, Instant};
 use crate::result::ResultExtension;
+use crate::rmc::connection_context::ConnectionContext;
+use crate::rmc::structures::limits::ReadLimits;
+use crate::versions::NexVersion;
 
 #[derive(Error, Debug)]
 pub enum RemoteCallError {
@@ -39,18 +46,91 @@ pub enum RemoteCallError {
     InvalidResponse(#[from] structures::Error),
 }
 
+/// How long to wait on a response if no other timeout was set.
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct RmcConnection(pub SendingBufferConnection, pub RmcResponseReceiver);
 
-pub struct RmcResponseReceiver(Arc<Notify>, Arc<Mutex<HashMap<u32, RMCResponse>>>);
+/// Calls which are still waiting on a response from the other side.
+struct PendingCalls{
+    // none once the gateway ended so that new calls fail right away
+    calls: Mutex<Option<HashMap<u32, oneshot::Sender<RMCResponse>>>>,
+}
+
+impl PendingCalls{
+    fn new() -> Self{
+        Self{
+            calls: Mutex::new(Some(HashMap::new())),
+        }
+    }
+
+    /// Registers a call, this has to happen before the request gets sent so that a fast response
+    /// cant get lost.
+    fn register(&self, call_id: u32) -> Result<oneshot::Receiver<RMCResponse>, RemoteCallError>{
+        let mut calls = self.calls.lock().expect("pending calls poisoned");
+
+        let calls = calls.as_mut().ok_or(ConnectionBroke)?;
+
+        let (sender, receiver) = oneshot::channel();
+
+        calls.insert(call_id, sender);
+
+        Ok(receiver)
+    }
+
+    fn remove(&self, call_id: u32){
+        if let Some(calls) = self.calls.lock().expect("pending calls poisoned").as_mut(){
+            calls.remove(&call_id);
+        }
+    }
+
+    /// Hands a response to whoever is waiting on it, returns false if noone is waiting anymore
+    /// (e.g. because the call timed out already).
+    fn complete(&self, response: RMCResponse) -> bool{
+        let sender = self.calls.lock().expect("pending calls poisoned")
+            .as_mut()
+            .and_then(|c| c.remove(&response.get_call_id()));
+
+        match sender{
+            Some(sender) => sender.send(response).is_ok(),
+            None => false
+        }
+    }
+
+    /// Fails every pending call with [`ConnectionBroke`] and every call made afterwards.
+    fn close(&self){
+        // dropping the senders wakes up everyone who is waiting
+        self.calls.lock().expect("pending calls poisoned").take();
+    }
+}
+
+pub struct RmcResponseReceiver{
+    pending: Arc<PendingCalls>,
+    next_call_id: AtomicU32,
+    timeout: Mutex<Duration>,
+}
 
 impl RmcConnection {
     pub async fn make_raw_call<T: RmcSerialize>(
         &self,
         message: &RMCMessage,
     ) -> Result<T, RemoteCallError> {
-        self.make_raw_call_no_response(message).await?;
+        self.make_raw_call_with_timeout(message, self.1.call_timeout()).await
+    }
 
-        let data = self.1.get_response_data(message.call_id).await?;
+    pub async fn make_raw_call_with_timeout<T: RmcSerialize>(
+        &self,
+        message: &RMCMessage,
+        timeout: Duration,
+    ) -> Result<T, RemoteCallError> {
+        let receiver = self.1.pending.register(message.call_id)?;
+
+        if let Err(e) = self.make_raw_call_no_response(message).await{
+            self.1.pending.remove(message.call_id);
+            return Err(e);
+        }
+
+        let data = self.1.get_response_data(message.call_id, receiver, timeout).await?;
 
         let out = <T as RmcSerialize>::deserialize(&mut Cursor::new(data))?;
 
@@ -68,57 +148,60 @@ impl RmcConnection {
         Ok(())
     }
 
+    /// Gets the call id for the next call on this connection.
+    pub fn next_call_id(&self) -> u32{
+        self.1.next_call_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Sets how long calls on this connection wait on a response by default.
+    pub fn set_call_timeout(&self, timeout: Duration){
+        *self.1.timeout.lock().expect("call timeout poisoned") = timeout;
+    }
+
     pub async fn disconnect(&self){
         self.0.disconnect().await;
     }
 }
 
 impl RmcResponseReceiver {
-    // returns none if timed out
-    pub async fn get_response_data(&self, call_id: u32) -> Result<Vec<u8>, RemoteCallError> {
-        let mut end_wait_time = Instant::now();
-        end_wait_time += Duration::from_secs(5);
-
-        let sleep_fut = sleep_until(end_wait_time);
-        tokio::pin!(sleep_fut);
-
-        let mut sleep_manual_unlock_fut = Instant::now();
-        sleep_manual_unlock_fut += Duration::from_secs(4);
-
-        let sleep_manual_unlock_fut = sleep_until(sleep_manual_unlock_fut);
-        tokio::pin!(sleep_manual_unlock_fut);
-
-        loop {
-            let mut locked = self.1.lock().await;
-
-            if let Some(v) = locked.remove(&call_id) {
-                match v.response_result{
-                    RMCResponseResult::Success {
-                        data,
-                        ..
-                    } => return Ok(data),
-                    RMCResponseResult::Error {
-                        error_code,
-                        ..
-                    } => return Err(RemoteCallError::ServerError(error_code))
-                }
-            }
-
-            drop(locked);
+    fn new(pending: Arc<PendingCalls>) -> Self{
+        Self{
+            pending,
+            next_call_id: AtomicU32::new(1),
+            timeout: Mutex::new(DEFAULT_CALL_TIMEOUT),
+        }
+    }
 
-            let notif_fut = self.0.notified();
+    fn call_timeout(&self) -> Duration{
+        *self.timeout.lock().expect("call timeout poisoned")
+    }
 
-            tokio::select! {
-                _ = &mut sleep_manual_unlock_fut => {
-                    continue;
-                }
-                _ = &mut sleep_fut => {
-                    return Err(RemoteCallError::Timeout);
-                }
-                _ = notif_fut => {
-                    continue;
-                }
+    async fn get_response_data(
+        &self,
+        call_id: u32,
+        receiver: oneshot::Receiver<RMCResponse>,
+        timeout: Duration
+    ) -> Result<Vec<u8>, RemoteCallError> {
+        let response = match tokio::time::timeout(timeout, receiver).await{
+            Ok(Ok(response)) => response,
+            // the sender only gets dropped without sending if the gateway ended
+            Ok(Err(_)) => return Err(ConnectionBroke),
+            Err(_) => {
+                // forget about the call so that a late response just gets dropped
+                self.pending.remove(call_id);
+                return Err(RemoteCallError::Timeout);
             }
+        };
+
+        match response.response_result{
+            RMCResponseResult::Success {
+                data,
+                ..
+            } => Ok(data),
+            RMCResponseResult::Error {
+                error_code,
+                ..
+            } => Err(RemoteCallError::ServerError(error_code))
         }
     }
 }
@@ -127,6 +210,112 @@ pub trait HasRmcConnection {
     fn get_connection(&self) -> &RmcConnection;
 }
 
+/// Names of a protocol and its methods, every `rmc_proto` has one of these as `Raw<Proto>Info::INFO`.
+#[derive(Debug, Copy, Clone)]
+pub struct ProtocolInfo{
+    pub id: u16,
+    pub name: &'static str,
+    pub methods: &'static [(u32, &'static str)],
+    /// false for `NoReturn` protocols whose calls never get a response
+    pub has_returns: bool,
+}
+
+/// Every nex protocol we know of, this is used to give calls readable names in logs.
+pub static KNOWN_PROTOCOLS: &[ProtocolInfo] = &[
+    nat_traversal::RawNatTraversalInfo::INFO,
+    nat_traversal::RawNatTraversalConsoleInfo::INFO,
+    auth::RawAuthInfo::INFO,
+    secure::RawSecureInfo::INFO,
+    notifications::RawNotificationInfo::INFO,
+    matchmake::RawMatchmakeInfo::INFO,
+    matchmake_ext::RawMatchmakeExtInfo::INFO,
+    matchmake_extension::RawMatchmakeExtensionInfo::INFO,
+    ranking::RawRankingInfo::INFO,
+];
+
+pub fn protocol_name(protocol_id: u16) -> Option<&'static str>{
+    KNOWN_PROTOCOLS.iter().find(|p| p.id == protocol_id).map(|p| p.name)
+}
+
+pub fn method_name(protocol_id: u16, method_id: u32) -> Option<&'static str>{
+    // some protocols (like nat traversal) are split into multiple traits so check all of them
+    KNOWN_PROTOCOLS.iter()
+        .filter(|p| p.id == protocol_id)
+        .flat_map(|p| p.methods)
+        .find(|(id, _)| *id == method_id)
+        .map(|(_, name)| *name)
+}
+
+/// Whether calls to a protocol expect a response, protocols we dont know are assumed to do.
+pub fn protocol_has_returns(protocol_id: u16) -> bool{
+    let mut protocols = KNOWN_PROTOCOLS.iter().filter(|p| p.id == protocol_id).peekable();
+
+    protocols.peek().is_none() || protocols.any(|p| p.has_returns)
+}
+
+/// How often each (protocol id, method id) pair was called without us implementing it.
+static UNIMPLEMENTED_CALLS: Lazy<Mutex<HashMap<(u16, u32), u64>>> = Lazy::new(Default::default);
+
+/// Methods we dont know the name of are chosen by the client, so only this many of them get
+/// their own count to keep a client from filling up the map.
+const MAX_UNKNOWN_UNIMPLEMENTED_CALLS: usize = 256;
+
+/// Every unknown method past [`MAX_UNKNOWN_UNIMPLEMENTED_CALLS`] gets counted here, protocol 0
+/// is the keepalive protocol so no actual call ends up with this id.
+pub const OTHER_UNIMPLEMENTED_CALLS: (u16, u32) = (0, 0);
+
+pub fn record_unimplemented_call(protocol_id: u16, method_id: u32){
+    let mut calls = UNIMPLEMENTED_CALLS.lock().expect("unimplemented call counter poisoned");
+
+    count_unimplemented_call(&mut calls, protocol_id, method_id);
+}
+
+fn count_unimplemented_call(calls: &mut HashMap<(u16, u32), u64>, protocol_id: u16, method_id: u32){
+    let mut key = (protocol_id, method_id);
+
+    if method_name(protocol_id, method_id).is_none() && !calls.contains_key(&key){
+        let unknown = calls.keys().filter(|(p, m)| method_name(*p, *m).is_none()).count();
+
+        if unknown >= MAX_UNKNOWN_UNIMPLEMENTED_CALLS{
+            key = OTHER_UNIMPLEMENTED_CALLS;
+        }
+    }
+
+    *calls.entry(key).or_default() += 1;
+}
+
+/// Gets the protocol id, method id and call count of every unimplemented method which got called
+/// so far, this shows which methods clients actually need.
+pub fn unimplemented_call_counts() -> Vec<(u16, u32, u64)>{
+    let calls = UNIMPLEMENTED_CALLS.lock().expect("unimplemented call counter poisoned");
+
+    let mut counts: Vec<_> = calls.iter().map(|(&(p, m), &c)| (p, m, c)).collect();
+    counts.sort_unstable();
+
+    counts
+}
+
+/// Responds to a call with `Core_NotImplemented` so the caller doesnt have to wait for the
+/// timeout, the call also gets counted. Calls to `NoReturn` protocols dont get a response as the
+/// other side doesnt expect one.
+pub async fn respond_not_implemented(responder: &SendingBufferConnection, protocol_id: u16, method_id: u32, call_id: u32){
+    warn!(
+        "unimplemented rmc call: protocol {} ({}) method {} ({})",
+        protocol_id,
+        protocol_name(protocol_id).unwrap_or("unknown"),
+        method_id,
+        method_name(protocol_id, method_id).unwrap_or("unknown")
+    );
+
+    record_unimplemented_call(protocol_id, method_id);
+
+    if !protocol_has_returns(protocol_id){
+        return;
+    }
+
+    send_result(responder, Err(ErrorCode::Core_NotImplemented), protocol_id, method_id, call_id).await;
+}
+
 pub trait RemoteObject {
     fn new(conn: RmcConnection) -> Self;
 }
@@ -157,11 +346,19 @@ macro_rules! define_rmc_proto {
                 async fn rmc_call(&self, remote_response_connection: &rust_nex::util::SendingBufferConnection, protocol_id: u16, method_id: u32, call_id: u32, rest: Vec<u8>){
                     match protocol_id{
                         $(
-                            [<Raw $protocol Info>]::PROTOCOL_ID => <Self as [<Raw $protocol>]>::rmc_call_proto(self, remote_response_connection, method_id, call_id, rest).await,
+                            [<Raw $protocol Info>]::PROTOCOL_ID if [<Raw $protocol Info>]::METHOD_IDS.contains(&method_id) =>
+                                <Self as [<Raw $protocol>]>::rmc_call_proto(self, remote_response_connection, method_id, call_id, rest).await,
                         )*
-                        v => log::error!("invalid protocol called on rmc object {}", v)
+                        _ => self.rmc_fallback(remote_response_connection, protocol_id, method_id, call_id, rest).await
                     }
                 }
+
+                /// Gets called for protocols and methods this object doesnt implement, by default
+                /// this responds with `Core_NotImplemented`. Use `#[rmc_struct(Proto, fallback = fn_name)]`
+                /// to handle these calls yourself.
+                async fn rmc_fallback(&self, remote_response_connection: &rust_nex::util::SendingBufferConnection, protocol_id: u16, method_id: u32, call_id: u32, _rest: Vec<u8>){
+                    rust_nex::rmc::protocols::respond_not_implemented(remote_response_connection, protocol_id, method_id, call_id).await
+                }
             }
 
             pub struct [<Remote $name>](rust_nex::rmc::protocols::RmcConnection);
@@ -192,13 +389,13 @@ macro_rules! define_rmc_proto {
 impl RmcCallable for () {
     async fn rmc_call(
         &self,
-        _remote_response_connection: &SendingBufferConnection,
-        _protocol_id: u16,
-        _method_id: u32,
-        _call_id: u32,
+        remote_response_connection: &SendingBufferConnection,
+        protocol_id: u16,
+        method_id: u32,
+        call_id: u32,
         _rest: Vec<u8>,
     ) {
-        //todo: maybe reply with not implemented(?)
+        respond_not_implemented(remote_response_connection, protocol_id, method_id, call_id).await
     }
 }
 
@@ -228,24 +425,164 @@ impl<T: RemoteInstantiatable> OnlyRemote<T>{
 }
 
 impl<T: RemoteInstantiatable> RmcCallable for OnlyRemote<T>{
-    fn rmc_call(&self, _responder: &SendingBufferConnection, _protocol_id: u16, _method_id: u32, _call_id: u32, _rest: Vec<u8>) -> impl Future<Output = ()> + Send {
-        // maybe respond with not implemented or something
-        async{}
+    fn rmc_call(&self, responder: &SendingBufferConnection, protocol_id: u16, method_id: u32, call_id: u32, _rest: Vec<u8>) -> impl Future<Output = ()> + Send {
+        // this object only exists to call the other side, it doesnt implement anything itself
+        respond_not_implemented(responder, protocol_id, method_id, call_id)
+    }
+}
+
+/// Settings for an rmc gateway, see [`new_rmc_gateway_connection_with_config`].
+#[derive(Clone)]
+pub struct RmcGatewayConfig{
+    /// how many incoming calls of a single connection may be handled (or queued up) at the same
+    /// time, calls beyond this get rejected with `Core_CallInitiationFailure`. we never stop
+    /// reading from the connection so responses to our own calls keep getting through.
+    pub max_concurrent_calls: usize,
+    /// protocols whose calls have to be handled one after another in the order they came in,
+    /// calls to every other protocol get handled concurrently
+    pub serialized_protocols: Vec<u16>,
+    /// get to see (and reject) every incoming call before it gets handled
+    pub interceptors: InterceptorChain,
+    /// who is on the other side, this gets passed on to the interceptors
+    pub caller: Option<CallerIdentity>,
+    /// the nex version of the other side if it differs from the default version of the game,
+    /// see [`crate::rmc::connection_context`]
+    pub nex_version: Option<NexVersion>,
+    /// limits for everything the other side sends us, see [`crate::rmc::connection_context`]
+    pub read_limits: ReadLimits,
+}
+
+impl Default for RmcGatewayConfig{
+    fn default() -> Self {
+        Self{
+            max_concurrent_calls: 16,
+            serialized_protocols: Vec::new(),
+            interceptors: InterceptorChain::new(),
+            caller: None,
+            nex_version: None,
+            read_limits: ReadLimits::default(),
+        }
+    }
+}
+
+/// Everything needed to handle an incoming call on its own task.
+struct CallHandler<T>{
+    remote: Arc<T>,
+    sending_conn: SendingBufferConnection,
+    interceptors: InterceptorChain,
+    caller: Option<CallerIdentity>,
+    context: ConnectionContext,
+}
+
+impl<T: RmcCallable> CallHandler<T>{
+    async fn run(&self, message: RMCMessage){
+        self.context.scope(self.handle(message)).await
+    }
+
+    async fn handle(&self, message: RMCMessage){
+        let RMCMessage{
+            protocol_id,
+            method_id,
+            call_id,
+            rest_of_data
+        } = message;
+
+        if self.interceptors.is_empty(){
+            self.remote.rmc_call(&self.sending_conn, protocol_id, method_id, call_id, rest_of_data).await;
+            return;
+        }
+
+        let call = IncomingCall{
+            protocol_id,
+            method_id,
+            call_id,
+            caller: self.caller.as_ref(),
+            parameters: &rest_of_data,
+        };
+
+        if let Err(error_code) = self.interceptors.before_call(&call).await{
+            if protocol_has_returns(protocol_id){
+                send_result(&self.sending_conn, Err(error_code), protocol_id, method_id, call_id).await;
+            }
+            return;
+        }
+
+        let start = Instant::now();
+
+        self.remote.rmc_call(&self.sending_conn, protocol_id, method_id, call_id, rest_of_data).await;
+
+        // the parameters are gone now, they got moved into the call
+        let call = IncomingCall{
+            protocol_id,
+            method_id,
+            call_id,
+            caller: self.caller.as_ref(),
+            parameters: &[],
+        };
+
+        self.interceptors.after_call(&call, start.elapsed()).await;
     }
 }
 
+/// Answers a call we dont have the capacity for right now without waiting on anything.
+fn reject_busy<T>(handler: &CallHandler<T>, message: RMCMessage){
+    warn!("too many calls in flight, rejecting call to protocol {} method {}", message.protocol_id, message.method_id);
+
+    if !protocol_has_returns(message.protocol_id){
+        return;
+    }
+
+    let sending_conn = handler.sending_conn.clone();
+
+    tokio::spawn(async move {
+        send_result(&sending_conn, Err(ErrorCode::Core_CallInitiationFailure), message.protocol_id, message.method_id, message.call_id).await;
+    });
+}
+
 async fn handle_incoming<T: RmcCallable + Send + Sync + 'static>(
     mut connection: SplittableBufferConnection,
     remote: Arc<T>,
-    notify: Arc<Notify>,
-    incoming: Arc<Mutex<HashMap<u32, RMCResponse>>>,
+    pending: Arc<PendingCalls>,
+    config: RmcGatewayConfig,
 ) {
-    let sending_conn = connection.duplicate_sender();
+    let context = ConnectionContext{
+        nex_version: config.nex_version,
+        read_limits: config.read_limits,
+    };
+
+    let handler = Arc::new(CallHandler{
+        remote,
+        sending_conn: connection.duplicate_sender(),
+        interceptors: config.interceptors,
+        caller: config.caller,
+        context,
+    });
+
+    // every call holds one of these from the moment it got read until it is done, so a client
+    // flooding us with calls gets its calls rejected instead of piling up tasks
+    let limit = Arc::new(Semaphore::new(config.max_concurrent_calls.max(1)));
+
+    // every serialized protocol gets its own queue which gets worked through in order, the
+    // workers stop once the queues get dropped at the end of this function
+    let serialized: HashMap<u16, mpsc::Sender<(RMCMessage, OwnedSemaphorePermit)>> = config.serialized_protocols.iter().map(|&protocol_id| {
+        let (queue, mut receiver) = mpsc::channel::<(RMCMessage, OwnedSemaphorePermit)>(config.max_concurrent_calls.max(1));
+
+        let handler = handler.clone();
+
+        tokio::spawn(async move {
+            while let Some((message, _permit)) = receiver.recv().await{
+                handler.run(message).await;
+            }
+        });
+
+        (protocol_id, queue)
+    }).collect();
 
     while let Some(v) = connection.recv().await{
         let Some(proto_id) = v.get(4) else {
             error!("received too small rmc message.");
             error!("ending rmc gateway.");
+            pending.close();
             return
         };
 
@@ -256,38 +593,66 @@ async fn handle_incoming<T: RmcCallable + Send + Sync + 'static>(
         }
 
         if (proto_id & 0x80) == 0{
-            let Some(response) = RMCResponse::new(&mut Cursor::new(v)).display_err_or_some() else {
+            let response = context.sync_scope(|| RMCResponse::new(&mut Cursor::new(v)));
+
+            let Some(response) = response.display_err_or_some() else {
                 error!("ending rmc gateway.");
+                pending.close();
                 return
             };
 
             info!("got rmc response");
 
-            let mut locked = incoming.lock().await;
+            let call_id = response.get_call_id();
 
-            locked.insert(response.get_call_id(), response);
-            notify.notify_waiters();
+            if !pending.complete(response){
+                warn!("dropping rmc response for call {} which isnt being waited on", call_id);
+            }
         } else {
-            let Some(message) = RMCMessage::new(&mut Cursor::new(v)).display_err_or_some() else {
+            let message = context.sync_scope(|| RMCMessage::new(&mut Cursor::new(v)));
+
+            let Some(message) = message.display_err_or_some() else {
                 error!("ending rmc gateway.");
+                pending.close();
                 return
             };
 
-            let RMCMessage{
-                protocol_id,
-                method_id,
-                call_id,
-                rest_of_data
-            } = message;
+            info!(
+                "RMC REQUEST: Proto: {} ({}); Method: {} ({});",
+                message.protocol_id,
+                protocol_name(message.protocol_id).unwrap_or("unknown"),
+                message.method_id,
+                method_name(message.protocol_id, message.method_id).unwrap_or("unknown")
+            );
+
+            // this loop must never wait on anything but the connection, otherwise handlers which
+            // wait on a response from the other side could end up waiting on themselves
+            let Ok(permit) = limit.clone().try_acquire_owned() else {
+                reject_busy(&handler, message);
+                continue;
+            };
 
-            info!("RMC REQUEST: Proto: {}; Method: {};", protocol_id, method_id);
+            // calls get handled on their own tasks so that a slow call doesnt hold up other
+            // calls or the responses to our own calls
+            if let Some(queue) = serialized.get(&message.protocol_id){
+                // every queued call holds a permit so the queue cant actually be full
+                if let Err(TrySendError::Full((message, _)) | TrySendError::Closed((message, _))) = queue.try_send((message, permit)){
+                    reject_busy(&handler, message);
+                }
+            } else {
+                let handler = handler.clone();
 
-            remote.rmc_call(&sending_conn, protocol_id, method_id, call_id, rest_of_data).await; 
+                tokio::spawn(async move {
+                    handler.run(message).await;
 
-            
+                    drop(permit);
+                });
+            }
         }
     }
-    
+
+    pending.close();
+
     info!("rmc disconnected")
 }
 
@@ -295,10 +660,16 @@ pub fn new_rmc_gateway_connection<T: RmcCallable + Sync + Send + 'static,F>(conn
 where
     F: FnOnce(RmcConnection) -> Arc<T>,
 {
-    let notify = Arc::new(Notify::new());
-    let incoming: Arc<Mutex<HashMap<u32, RMCResponse>>> = Default::default();
+    new_rmc_gateway_connection_with_config(conn, RmcGatewayConfig::default(), create_internal)
+}
+
+pub fn new_rmc_gateway_connection_with_config<T: RmcCallable + Sync + Send + 'static,F>(conn: SplittableBufferConnection, config: RmcGatewayConfig, create_internal: F) -> Arc<T>
+where
+    F: FnOnce(RmcConnection) -> Arc<T>,
+{
+    let pending = Arc::new(PendingCalls::new());
 
-    let response_recv = RmcResponseReceiver(notify.clone(), incoming.clone());
+    let response_recv = RmcResponseReceiver::new(pending.clone());
 
     let sending_conn = conn.duplicate_sender();
 
@@ -314,8 +685,8 @@ where
             handle_incoming(
                 conn,
                 exposed_object,
-                notify,
-                incoming
+                pending,
+                config
             ).await;
         });
 
@@ -331,6 +702,23 @@ where
     exposed_object
 }
 
+/// Exposes the object made by `create_local` on one end of an in-memory connection and gives back
+/// the remote object for the other end, this way protocol implementations can get tested
+/// without any sockets.
+pub fn new_local_rmc_pair<L, R, F>(create_local: F) -> (Arc<L>, Arc<OnlyRemote<R>>)
+where
+    L: RmcCallable + Sync + Send + 'static,
+    R: RemoteInstantiatable + Sync + Send + 'static,
+    F: FnOnce(RmcConnection) -> Arc<L>,
+{
+    let (local_conn, remote_conn) = SplittableBufferConnection::pair();
+
+    let local = new_rmc_gateway_connection(local_conn, create_local);
+    let remote = new_rmc_gateway_connection(remote_conn, |r| Arc::new(OnlyRemote::<R>::new(r)));
+
+    (local, remote)
+}
+
 impl<T: RmcCallable> RmcCallable for Arc<T>{
     fn rmc_call(&self, responder: &SendingBufferConnection, protocol_id: u16, method_id: u32, call_id: u32, rest: Vec<u8>) -> impl Future<Output=()> + Send {
         self.as_ref().rmc_call(responder, protocol_id, method_id, call_id, rest)
@@ -339,4 +727,245 @@ impl<T: RmcCallable> RmcCallable for Arc<T>{
 
 define_rmc_proto! {
     proto NoProto{}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test{
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use async_trait::async_trait;
+    use macros::rmc_struct;
+    use tokio::time::sleep;
+    use crate::rmc::interceptor::{IncomingCall, InterceptorChain, RmcInterceptor};
+    use crate::rmc::message::RMCMessage;
+    use crate::rmc::protocols::{count_unimplemented_call, method_name, new_local_rmc_pair, MAX_UNKNOWN_UNIMPLEMENTED_CALLS, OTHER_UNIMPLEMENTED_CALLS, new_rmc_gateway_connection, new_rmc_gateway_connection_with_config, protocol_name, record_unimplemented_call, unimplemented_call_counts, HasRmcConnection, OnlyRemote, PendingCalls, RemoteCallError, RemoteNoProto, RmcCallable, RmcGatewayConfig};
+    use crate::rmc::response::{send_result, ErrorCode, RMCResponse, RMCResponseResult};
+    use crate::rmc::structures::RmcSerialize;
+    use crate::util::{SendingBufferConnection, SplittableBufferConnection};
+
+    define_rmc_proto! {
+        proto FallbackTest{}
+    }
+
+    #[rmc_struct(FallbackTest, fallback = unknown_call)]
+    struct FallbackObject;
+
+    impl FallbackObject{
+        /// Answers every call with its method id.
+        async fn unknown_call(&self, responder: &SendingBufferConnection, protocol_id: u16, method_id: u32, call_id: u32, _rest: Vec<u8>){
+            send_result(responder, Ok(method_id.to_data()), protocol_id, method_id, call_id).await;
+        }
+    }
+
+    fn call_message(remote: &impl HasRmcConnection, protocol_id: u16, method_id: u32) -> RMCMessage{
+        RMCMessage{
+            protocol_id,
+            method_id,
+            call_id: remote.get_connection().next_call_id(),
+            rest_of_data: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback(){
+        let (_local, remote) = new_local_rmc_pair::<_, RemoteFallbackTest, _>(|_| Arc::new(FallbackObject));
+
+        let call = call_message(&**remote, 0x7FF, 7);
+
+        assert_eq!(remote.get_connection().make_raw_call::<u32>(&call).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn no_response_for_no_return_protocols(){
+        let (_local, remote) = new_local_rmc_pair::<_, RemoteNoProto, _>(|_| Arc::new(()));
+
+        // notifications never get a response, not even an error
+        let call = call_message(&**remote, 14, 0xFFF0);
+
+        assert!(matches!(
+            remote.get_connection().make_raw_call_with_timeout::<()>(&call, Duration::from_millis(200)).await,
+            Err(RemoteCallError::Timeout)
+        ));
+        assert!(unimplemented_call_counts().contains(&(14, 0xFFF0, 1)));
+    }
+
+    #[test]
+    fn counts_unimplemented_calls(){
+        record_unimplemented_call(0xFFF0, 1);
+        record_unimplemented_call(0xFFF0, 1);
+        record_unimplemented_call(0xFFF0, 2);
+
+        let counts = unimplemented_call_counts();
+
+        assert!(counts.contains(&(0xFFF0, 1, 2)));
+        assert!(counts.contains(&(0xFFF0, 2, 1)));
+    }
+
+    #[test]
+    fn unknown_unimplemented_calls_are_capped(){
+        let mut calls = HashMap::new();
+
+        for method_id in 0..1000{
+            count_unimplemented_call(&mut calls, 0xFFF0, method_id);
+        }
+
+        // known methods always get their own count
+        count_unimplemented_call(&mut calls, 109, 1);
+
+        assert_eq!(calls.len(), MAX_UNKNOWN_UNIMPLEMENTED_CALLS + 1);
+        assert_eq!(calls[&OTHER_UNIMPLEMENTED_CALLS], 1000 - MAX_UNKNOWN_UNIMPLEMENTED_CALLS as u64 + 1);
+        assert_eq!(calls[&(109, 1)], 1);
+    }
+
+    #[test]
+    fn method_names(){
+        assert_eq!(protocol_name(109), Some("MatchmakeExtension"));
+        assert_eq!(method_name(10, 2), Some("login_ex"));
+        // nat traversal is split between the server and console trait
+        assert_eq!(method_name(3, 5), Some("report_nat_properties"));
+        assert_eq!(method_name(0x7FFF, 1), None);
+    }
+
+    fn response(call_id: u32) -> RMCResponse{
+        RMCResponse{
+            protocol_id: 1,
+            response_result: RMCResponseResult::Success {
+                call_id,
+                method_id: 1,
+                data: vec![1, 2, 3],
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn pending_calls(){
+        let pending = PendingCalls::new();
+
+        let receiver = pending.register(1).unwrap();
+
+        assert!(pending.complete(response(1)));
+        assert_eq!(receiver.await.unwrap().get_call_id(), 1);
+
+        // late responses for calls noone waits on anymore get dropped
+        let _receiver = pending.register(2).unwrap();
+        pending.remove(2);
+        assert!(!pending.complete(response(2)));
+
+        let receiver = pending.register(3).unwrap();
+        pending.close();
+
+        assert!(receiver.await.is_err());
+        assert!(matches!(pending.register(4), Err(RemoteCallError::ConnectionBroke)));
+    }
+
+    /// Takes a while to answer every call and keeps track of how many calls ran at once.
+    #[derive(Default)]
+    struct SlowObject{
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+        finished: Mutex<Vec<u32>>,
+    }
+
+    impl RmcCallable for SlowObject{
+        async fn rmc_call(&self, responder: &SendingBufferConnection, protocol_id: u16, method_id: u32, call_id: u32, _rest: Vec<u8>){
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+
+            sleep(Duration::from_millis(50)).await;
+
+            self.finished.lock().unwrap().push(method_id);
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            send_result(responder, Ok(Vec::new()), protocol_id, method_id, call_id).await;
+        }
+    }
+
+    async fn call_slow_object(config: RmcGatewayConfig, protocol_id: u16) -> (Arc<SlowObject>, Vec<Result<(), RemoteCallError>>){
+        let (local_conn, remote_conn) = SplittableBufferConnection::pair();
+
+        let local = new_rmc_gateway_connection_with_config(local_conn, config, |_| Arc::new(SlowObject::default()));
+        let remote = new_rmc_gateway_connection(remote_conn, |r| Arc::new(OnlyRemote::<RemoteNoProto>::new(r)));
+
+        let calls: Vec<_> = (1..=4).map(|method_id| call_message(&**remote, protocol_id, method_id)).collect();
+
+        let results = futures::future::join_all(
+            calls.iter().map(|call| remote.get_connection().make_raw_call::<()>(call))
+        ).await;
+
+        (local, results)
+    }
+
+    struct DenyAll;
+
+    #[async_trait]
+    impl RmcInterceptor for DenyAll{
+        async fn before_call(&self, _call: &IncomingCall<'_>) -> Result<(), ErrorCode> {
+            Err(ErrorCode::Core_AccessDenied)
+        }
+    }
+
+    #[tokio::test]
+    async fn interceptors_short_circuit_calls(){
+        let config = RmcGatewayConfig{
+            interceptors: InterceptorChain::new().with(DenyAll),
+            ..Default::default()
+        };
+
+        let (local_conn, remote_conn) = SplittableBufferConnection::pair();
+
+        let local = new_rmc_gateway_connection_with_config(local_conn, config, |_| Arc::new(SlowObject::default()));
+        let remote = new_rmc_gateway_connection(remote_conn, |r| Arc::new(OnlyRemote::<RemoteNoProto>::new(r)));
+
+        let call = call_message(&**remote, 100, 1);
+
+        assert!(matches!(
+            remote.get_connection().make_raw_call::<()>(&call).await,
+            Err(RemoteCallError::ServerError(ErrorCode::Core_AccessDenied))
+        ));
+
+        // rejected notifications dont get an answer either
+        let call = call_message(&**remote, 14, 1);
+
+        assert!(matches!(
+            remote.get_connection().make_raw_call_with_timeout::<()>(&call, Duration::from_millis(200)).await,
+            Err(RemoteCallError::Timeout)
+        ));
+
+        assert!(local.finished.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn concurrent_calls_are_limited(){
+        let config = RmcGatewayConfig{
+            max_concurrent_calls: 2,
+            ..Default::default()
+        };
+
+        let (local, results) = call_slow_object(config, 100).await;
+
+        assert_eq!(local.max_running.load(Ordering::SeqCst), 2);
+
+        // the calls over the limit get turned away right away instead of holding up the connection
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 2);
+        assert_eq!(
+            results.iter().filter(|r| matches!(r, Err(RemoteCallError::ServerError(ErrorCode::Core_CallInitiationFailure)))).count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn serialized_calls_run_in_order(){
+        let config = RmcGatewayConfig{
+            serialized_protocols: vec![100],
+            ..Default::default()
+        };
+
+        let (local, results) = call_slow_object(config, 100).await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(local.max_running.load(Ordering::SeqCst), 1);
+        assert_eq!(*local.finished.lock().unwrap(), vec![1, 2, 3, 4]);
+    }
+}
@@ -9,7 +9,9 @@ use bytemuck::bytes_of;
 use log::error;
 use v_byte_macros::EnumTryInto;
 use crate::endianness::{ReadExtensions, IS_BIG_ENDIAN};
+use crate::rmc::message::{protocol_id_size, write_protocol_id, EXTENDED_PROTOCOL_ID};
 use crate::rmc::response::ErrorCode::Core_Exception;
+use crate::rmc::structures::limits::check_size;
 use crate::rmc::structures::qresult::ERROR_MASK;
 use crate::util::SendingBufferConnection;
 
@@ -26,7 +28,7 @@ pub enum RMCResponseResult {
 }
 
 pub struct RMCResponse {
-    pub protocol_id: u8,
+    pub protocol_id: u16,
     pub response_result: RMCResponseResult,
 }
 
@@ -35,14 +37,18 @@ impl RMCResponse {
         // ignore the size for now this will only be used for checking
         let size: u32 = stream.read_struct(IS_BIG_ENDIAN)?;
 
+        check_size("rmc response size", size as usize)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
         let protocol_id: u8 = stream.read_struct(IS_BIG_ENDIAN)?;
+        let protocol_id = protocol_id & (!0x80);
 
-        /*let protocol_id: u16 = match protocol_id{
-            0x7F => {
+        let protocol_id: u16 = match protocol_id{
+            EXTENDED_PROTOCOL_ID => {
                 stream.read_struct(IS_BIG_ENDIAN)?
             },
             _ => protocol_id as u16
-        };*/
+        };
 
         let is_success: u8 = stream.read_struct(IS_BIG_ENDIAN)?;
 
@@ -51,9 +57,15 @@ impl RMCResponse {
             let method_id: u32 = stream.read_struct(IS_BIG_ENDIAN)?;
             let method_id = method_id & (!0x8000);
 
-            let mut data: Vec<u8> = vec![0u8; (size - 2 - 4 - 4) as _];
+            let header_size = protocol_id_size(protocol_id) + 1 + 4 + 4;
+
+            let Some(data_size) = (size as usize).checked_sub(header_size) else {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "rmc response is smaller than its header"));
+            };
+
+            let mut data: Vec<u8> = vec![0u8; data_size];
 
-            stream.read(&mut data)?;
+            stream.read_exact(&mut data)?;
 
 
             RMCResponseResult::Success {
@@ -99,8 +111,8 @@ impl RMCResponse {
     }
 }
 
-pub fn generate_response(protocol_id: u8, response: RMCResponseResult) -> io::Result<Vec<u8>> {
-    let size = 1 + 1 + match &response {
+pub fn generate_response(protocol_id: u16, response: RMCResponseResult) -> io::Result<Vec<u8>> {
+    let size = protocol_id_size(protocol_id) + 1 + match &response {
         RMCResponseResult::Success {
             data,
             ..
@@ -113,7 +125,7 @@ pub fn generate_response(protocol_id: u8, response: RMCResponseResult) -> io::Re
     let u32_size: u32 = size as _;
 
     data_out.write_all(bytes_of(&u32_size))?;
-    data_out.push(protocol_id);
+    write_protocol_id(&mut data_out, protocol_id, 0);
 
     match response {
         RMCResponseResult::Success {
@@ -147,7 +159,7 @@ pub fn generate_response(protocol_id: u8, response: RMCResponseResult) -> io::Re
 pub async fn send_result(
     connection: &SendingBufferConnection,
     result: Result<Vec<u8>, ErrorCode>,
-    protocol_id: u8,
+    protocol_id: u16,
     method_id: u32,
     call_id: u32,
 ) {
@@ -181,7 +193,7 @@ pub async fn send_response(connection: &SendingBufferConnection, rmcresponse: RM
 //taken from kinnays error list directly
 #[allow(nonstandard_style)]
 #[repr(u32)]
-#[derive(Debug, EnumTryInto, Clone, Copy)]
+#[derive(Debug, EnumTryInto, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorCode {
     Core_Unknown = 0x00010001,
     Core_NotImplemented = 0x00010002,
@@ -467,7 +479,8 @@ mod test {
     use hmac::digest::consts::U5;
     use hmac::digest::KeyInit;
     use rc4::{Rc4, StreamCipher};
-    use crate::rmc::response::ErrorCode;
+    use std::io::Cursor;
+    use crate::rmc::response::{ErrorCode, RMCResponse, RMCResponseResult};
 
     #[test]
     fn test() {
@@ -489,6 +502,30 @@ mod test {
         assert_eq!(data_orig, data);
     }
 
+    #[test]
+    fn extended_protocol_id_responses() {
+        for protocol_id in [10, 0x7F, 200, 0x1234] {
+            let response = RMCResponse {
+                protocol_id,
+                response_result: RMCResponseResult::Success {
+                    call_id: 5,
+                    method_id: 7,
+                    data: vec![1, 2, 3],
+                },
+            };
+
+            let response = RMCResponse::new(&mut Cursor::new(response.to_data())).unwrap();
+
+            assert_eq!(response.protocol_id, protocol_id);
+
+            let RMCResponseResult::Success { call_id, method_id, data } = response.response_result else {
+                panic!("expected a successful response");
+            };
+
+            assert_eq!((call_id, method_id, data), (5, 7, vec![1, 2, 3]));
+        }
+    }
+
     #[test]
     fn test_enum_equivilance() {
         let val: u32 = ErrorCode::Core_Unknown.into();
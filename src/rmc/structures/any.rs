@@ -1,33 +1,79 @@
+use std::any::Any as StdAny;
+use std::collections::HashMap;
+use std::fmt::Debug;
 use std::io::{Read, Write};
+use bytemuck::bytes_of;
+use once_cell::sync::Lazy;
 use crate::endianness::{IS_BIG_ENDIAN, ReadExtensions};
-use super::{Result, RmcSerialize};
+use crate::rmc::structures::authentication_info::AuthenticationInfo;
+use super::limits::check_size;
+use super::{Error, Result, RmcSerialize};
 
-#[derive(Debug, Default)]
+/// A type which can be sent through an `AnyDataHolder`, the type name is what the holder gets
+/// tagged with on the wire.
+pub trait AnyDataType: RmcSerialize + Debug + Send + Sync + 'static{
+    const TYPE_NAME: &'static str;
+}
+
+/// The raw contents of an `AnyDataHolder`, use [`Any::decode`] if you know which type to expect
+/// or [`Any::decode_dynamic`] if you dont.
+#[derive(Debug, Default, Clone)]
 pub struct Any{
     pub name: String,
     pub data: Vec<u8>
 }
 
+impl Any{
+    pub fn encode<T: AnyDataType>(value: &T) -> Self{
+        Self{
+            name: T::TYPE_NAME.to_owned(),
+            data: value.to_data(),
+        }
+    }
+
+    pub fn decode<T: AnyDataType>(&self) -> Result<T>{
+        if self.name != T::TYPE_NAME {
+            return Err(Error::AnyTypeMismatch{
+                expected: T::TYPE_NAME,
+                found: self.name.clone(),
+            });
+        }
+
+        T::deserialize(&mut &self.data[..])
+    }
+
+    /// Decodes the contents with the type registered for its name in [`ANY_REGISTRY`].
+    pub fn decode_dynamic(&self) -> Result<AnyValue>{
+        ANY_REGISTRY.decode(self)
+    }
+}
+
 impl RmcSerialize for Any{
     fn serialize(&self, writer: &mut dyn Write) -> Result<()> {
         self.name.serialize(writer)?;
 
         let u32_len = self.data.len() as u32;
 
-        u32_len.serialize(writer)?;
-        u32_len.serialize(writer)?;
+        // the outer length also counts the inner length
+        writer.write_all(bytes_of(&(u32_len + 4)))?;
+        writer.write_all(bytes_of(&u32_len))?;
 
-        self.data.serialize(writer)?;
+        writer.write_all(&self.data)?;
 
         Ok(())
     }
     fn deserialize(mut reader: &mut dyn Read) -> Result<Self> {
         let name = String::deserialize(reader)?;
 
-        // also length ?
-        let _len2: u32 = reader.read_struct(IS_BIG_ENDIAN)?;
+        let outer_length: u32 = reader.read_struct(IS_BIG_ENDIAN)?;
         let length: u32 = reader.read_struct(IS_BIG_ENDIAN)?;
 
+        if length.checked_add(4) != Some(outer_length) {
+            return Err(Error::AnyLengthMismatch(outer_length, length));
+        }
+
+        check_size("any data size", length as usize)?;
+
         let mut data = vec![0; length as usize];
 
         reader.read_exact(&mut data)?;
@@ -39,4 +85,135 @@ impl RmcSerialize for Any{
             }
         )
     }
-}
\ No newline at end of file
+}
+
+/// Object safe version of [`AnyDataType`] so that values of any registered type can be passed
+/// around.
+pub trait DynAnyData: Debug + Send + Sync{
+    fn type_name(&self) -> &'static str;
+    fn as_any(&self) -> &dyn StdAny;
+    fn encode(&self) -> Any;
+}
+
+impl<T: AnyDataType> DynAnyData for T{
+    fn type_name(&self) -> &'static str {
+        T::TYPE_NAME
+    }
+
+    fn as_any(&self) -> &dyn StdAny {
+        self
+    }
+
+    fn encode(&self) -> Any {
+        Any::encode(self)
+    }
+}
+
+/// The decoded contents of an `AnyDataHolder`, types which arent registered are kept as is.
+#[derive(Debug)]
+pub enum AnyValue{
+    Known(Box<dyn DynAnyData>),
+    Unknown(Any),
+}
+
+impl AnyValue{
+    pub fn type_name(&self) -> &str{
+        match self{
+            Self::Known(v) => v.type_name(),
+            Self::Unknown(v) => &v.name,
+        }
+    }
+
+    pub fn downcast_ref<T: AnyDataType>(&self) -> Option<&T>{
+        match self{
+            Self::Known(v) => v.as_any().downcast_ref(),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    pub fn encode(&self) -> Any{
+        match self{
+            Self::Known(v) => v.encode(),
+            Self::Unknown(v) => v.clone(),
+        }
+    }
+}
+
+type Decoder = fn(&[u8]) -> Result<Box<dyn DynAnyData>>;
+
+/// Maps the type names used in `AnyDataHolder`s to the types they stand for.
+#[derive(Default)]
+pub struct AnyRegistry{
+    decoders: HashMap<&'static str, Decoder>,
+}
+
+impl AnyRegistry{
+    pub fn new() -> Self{
+        Self::default()
+    }
+
+    /// A registry with every type we know of.
+    pub fn with_known_types() -> Self{
+        let mut registry = Self::new();
+
+        registry.register::<AuthenticationInfo>();
+
+        registry
+    }
+
+    pub fn register<T: AnyDataType>(&mut self){
+        self.decoders.insert(T::TYPE_NAME, |mut data| {
+            Ok(Box::new(T::deserialize(&mut data)?))
+        });
+    }
+
+    pub fn decode(&self, any: &Any) -> Result<AnyValue>{
+        let Some(decoder) = self.decoders.get(any.name.as_str()) else {
+            return Ok(AnyValue::Unknown(any.clone()));
+        };
+
+        Ok(AnyValue::Known(decoder(&any.data)?))
+    }
+}
+
+pub static ANY_REGISTRY: Lazy<AnyRegistry> = Lazy::new(AnyRegistry::with_known_types);
+
+#[cfg(test)]
+mod test{
+    use crate::rmc::structures::any::{Any, AnyValue};
+    use crate::rmc::structures::authentication_info::AuthenticationInfo;
+    use crate::rmc::structures::{Error, RmcSerialize};
+
+    #[test]
+    fn any_data_holder(){
+        let info = AuthenticationInfo{
+            token: "token".to_owned(),
+            ngs_version: 2,
+            server_version: 30502,
+            ..Default::default()
+        };
+
+        let any = Any::encode(&info);
+        let data = any.to_data();
+        let any = Any::deserialize(&mut &data[..]).unwrap();
+
+        assert_eq!(any.decode::<AuthenticationInfo>().unwrap().token, "token");
+        assert_eq!(
+            any.decode_dynamic().unwrap().downcast_ref::<AuthenticationInfo>().unwrap().server_version,
+            30502
+        );
+
+        let unknown = Any{
+            name: "SomethingElse".to_owned(),
+            data: vec![1, 2, 3],
+        };
+
+        assert!(matches!(unknown.decode_dynamic().unwrap(), AnyValue::Unknown(_)));
+        assert!(matches!(unknown.decode::<AuthenticationInfo>(), Err(Error::AnyTypeMismatch{ .. })));
+
+        // the outer length has to be the inner length plus 4
+        let mut broken = unknown.to_data();
+        broken[16] += 1;
+        assert!(matches!(Any::deserialize(&mut &broken[..]), Err(Error::AnyLengthMismatch(..))));
+    }
+}
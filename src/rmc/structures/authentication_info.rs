@@ -0,0 +1,28 @@
+use macros::RmcSerialize;
+use crate::rmc::structures::any::AnyDataType;
+use crate::rmc::structures::data::Data;
+use crate::versions::NexVersion;
+
+/// The `AuthenticationInfo` data holder which clients pass to `LoginWithContext`(for details see
+/// the [kinnay wiki entry](https://github.com/kinnay/NintendoClients/wiki/Authentication-Protocol))
+#[derive(RmcSerialize, Debug, Clone, Default)]
+#[rmc_struct(0)]
+pub struct AuthenticationInfo{
+    #[extends]
+    pub data: Data,
+
+    pub token: String,
+    pub token_type: u32,
+    pub ngs_version: u8,
+    pub server_version: u32,
+}
+
+impl AnyDataType for AuthenticationInfo{
+    const TYPE_NAME: &'static str = "AuthenticationInfo";
+}
+
+impl AuthenticationInfo{
+    pub fn nex_version(&self) -> NexVersion{
+        NexVersion::from_packed(self.server_version)
+    }
+}
@@ -1,7 +1,9 @@
 
+use std::io::{Read, Write};
 use macros::RmcSerialize;
 use crate::kerberos::KerberosDateTime;
 use crate::rmc::structures::RmcSerialize;
+use crate::versions::{with_nex_version_sync, NexVersion};
 
 #[derive(Debug, RmcSerialize)]
 #[rmc_struct(1)]
@@ -12,3 +14,65 @@ pub struct ConnectionData{
     pub date_time: KerberosDateTime
 }
 
+/// [`ConnectionData`] which gets encoded in the layout the nex version of the client expects,
+/// clients older than nex 3.5 neither know about structure headers nor the server time.
+#[derive(Debug)]
+pub struct VersionedConnectionData{
+    pub nex_version: NexVersion,
+    pub connection_data: ConnectionData,
+}
+
+impl RmcSerialize for VersionedConnectionData{
+    fn serialize(&self, writer: &mut dyn Write) -> crate::rmc::structures::Result<()> {
+        if self.nex_version.has_structure_headers(){
+            // make sure the structure header gets written even if the connection says otherwise
+            return with_nex_version_sync(self.nex_version, || self.connection_data.serialize(writer));
+        }
+
+        self.connection_data.station_url.serialize(writer)?;
+        self.connection_data.special_protocols.serialize(writer)?;
+        self.connection_data.special_station_url.serialize(writer)?;
+
+        Ok(())
+    }
+
+    /// As the version isnt known when reading this assumes the layout of nex 3.5 and up.
+    fn deserialize(reader: &mut dyn Read) -> crate::rmc::structures::Result<Self> {
+        Ok(Self{
+            nex_version: NexVersion::new(3, 5, 0),
+            connection_data: ConnectionData::deserialize(reader)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::kerberos::KerberosDateTime;
+    use crate::rmc::structures::connection_data::{ConnectionData, VersionedConnectionData};
+    use crate::rmc::structures::RmcSerialize;
+    use crate::versions::NexVersion;
+
+    #[test]
+    fn old_versions_dont_get_headers(){
+        let connection_data = ConnectionData{
+            station_url: "a".to_string(),
+            special_protocols: Vec::new(),
+            special_station_url: "".to_string(),
+            date_time: KerberosDateTime::now(),
+        };
+
+        let old = VersionedConnectionData{
+            nex_version: NexVersion::from_packed(30400),
+            connection_data,
+        };
+
+        assert_eq!(old.to_data(), vec![2, 0, b'a', 0, 0, 0, 0, 0, 1, 0, 0]);
+
+        let new = VersionedConnectionData{
+            nex_version: NexVersion::from_packed(30500),
+            ..old
+        };
+
+        assert_eq!(new.to_data(), new.connection_data.to_data());
+    }
+}
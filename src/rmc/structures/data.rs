@@ -0,0 +1,38 @@
+use std::io::{Read, Write};
+use crate::rmc::structures::rmc_struct::{read_struct, write_struct};
+use crate::rmc::structures::RmcSerialize;
+
+/// The `Data` base class which every structure sent through an `AnyDataHolder` inherits from, it
+/// has no fields of its own but still gets its own structure header.
+///
+/// `Data<T>` is `T` with `Data` as its base class, this is for types which dont declare the
+/// inheritance themselves with `#[extends]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Data<T = ()>(pub T);
+
+impl<T: RmcSerialize> RmcSerialize for Data<T>{
+    fn serialize(&self, writer: &mut dyn Write) -> crate::rmc::structures::Result<()> {
+        write_struct(writer, 0, |_| Ok(()))?;
+
+        self.0.serialize(writer)
+    }
+
+    fn deserialize(reader: &mut dyn Read) -> crate::rmc::structures::Result<Self> {
+        read_struct(reader, 0, |_| Ok(()))?;
+
+        Ok(Self(T::deserialize(reader)?))
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::rmc::structures::data::Data;
+    use crate::rmc::structures::RmcSerialize;
+
+    #[test]
+    fn header_before_value(){
+        let data = Data(5u32).to_data();
+        assert_eq!(data, [0, 0, 0, 0, 0, 5, 0, 0, 0]);
+        assert_eq!(Data::<u32>::deserialize(&mut &data[..]).unwrap(), Data(5));
+    }
+}
@@ -0,0 +1,36 @@
+use std::io::{Read, Write};
+use chrono::{DateTime, Utc};
+use crate::kerberos::KerberosDateTime;
+use crate::rmc::structures::RmcSerialize;
+
+// nex only has the packed `DateTime` which is the same as `KerberosDateTime`, this lets
+// protocols use regular chrono times instead
+
+impl RmcSerialize for DateTime<Utc>{
+    fn serialize(&self, writer: &mut dyn Write) -> crate::rmc::structures::Result<()> {
+        KerberosDateTime::from(*self).serialize(writer)
+    }
+
+    fn deserialize(reader: &mut dyn Read) -> crate::rmc::structures::Result<Self> {
+        Ok(KerberosDateTime::deserialize(reader)?.to_regular_time()?)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use chrono::{DateTime, TimeZone, Utc};
+    use crate::rmc::structures::RmcSerialize;
+
+    #[test]
+    fn date_time_round_trip(){
+        let time = Utc.with_ymd_and_hms(2024, 7, 1, 8, 30, 5).unwrap();
+
+        let data = time.to_data();
+
+        // second | minute << 6 | hour << 12 | day << 17 | month << 22 | year << 26
+        let packed: u64 = 5 | (30 << 6) | (8 << 12) | (1 << 17) | (7 << 22) | (2024 << 26);
+
+        assert_eq!(data, packed.to_le_bytes());
+        assert_eq!(DateTime::<Utc>::deserialize(&mut &data[..]).unwrap(), time);
+    }
+}
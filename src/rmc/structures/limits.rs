@@ -0,0 +1,114 @@
+use crate::rmc::connection_context::ConnectionContext;
+use crate::rmc::structures::{Error, Result};
+
+/// Upper bounds for everything a client can make us allocate or loop over while deserializing,
+/// without these a single length field is enough to make the server allocate gigabytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits{
+    /// max size of a single message, buffer or structure in bytes
+    pub max_size: usize,
+    /// max amount of elements in a list or map
+    pub max_elements: u32,
+    /// max length of a string including the null terminator
+    pub max_string_length: u16,
+}
+
+impl Default for ReadLimits{
+    fn default() -> Self {
+        Self{
+            max_size: 1024 * 1024,
+            max_elements: 1024 * 1024,
+            max_string_length: 8192,
+        }
+    }
+}
+
+/// We never preallocate more than this amount of elements as the length of a list is not
+/// guaranteed to be backed by actual data.
+pub const MAX_PREALLOCATED_ELEMENTS: usize = 1024;
+
+/// Gets the limits of the connection which is currently being handled, see [`ConnectionContext`].
+pub fn read_limits() -> ReadLimits{
+    ConnectionContext::current().read_limits
+}
+
+/// Runs `f` with `limits` as the [`read_limits`], the rest of the [`ConnectionContext`] stays
+/// the same.
+pub fn with_read_limits_sync<R>(limits: ReadLimits, f: impl FnOnce() -> R) -> R{
+    ConnectionContext{
+        read_limits: limits,
+        ..ConnectionContext::current()
+    }.sync_scope(f)
+}
+
+fn check(what: &'static str, size: u64, max: u64) -> Result<()>{
+    if size > max {
+        return Err(Error::LimitExceeded { what, size, max });
+    }
+
+    Ok(())
+}
+
+pub fn check_size(what: &'static str, size: usize) -> Result<()>{
+    check(what, size as u64, read_limits().max_size as u64)
+}
+
+pub fn check_elements(len: u32) -> Result<()>{
+    check("element count", len as u64, read_limits().max_elements as u64)
+}
+
+pub fn check_string_length(len: u16) -> Result<()>{
+    check("string length", len as u64, read_limits().max_string_length as u64)
+}
+
+#[cfg(test)]
+mod test{
+    use std::collections::HashMap;
+    use std::io::Cursor;
+    use crate::rmc::message::RMCMessage;
+    use crate::rmc::response::RMCResponse;
+    use crate::rmc::structures::any::Any;
+    use crate::rmc::structures::limits::{with_read_limits_sync, ReadLimits};
+    use crate::rmc::structures::qbuffer::QBuffer;
+    use crate::rmc::structures::{Error, RmcSerialize};
+
+    #[test]
+    fn empty_string(){
+        assert_eq!(String::deserialize(&mut &[0u8, 0][..]).unwrap(), "");
+        assert_eq!(String::deserialize(&mut &[1u8, 0, 0][..]).unwrap(), "");
+    }
+
+    #[test]
+    fn response_smaller_than_header(){
+        let response = [&1u32.to_le_bytes()[..], &[0x01, 0x01], &1u32.to_le_bytes(), &1u32.to_le_bytes()].concat();
+
+        assert!(RMCResponse::new(&mut Cursor::new(response)).is_err());
+    }
+
+    #[test]
+    fn huge_lengths(){
+        let limits = ReadLimits{
+            max_size: 16,
+            max_elements: 4,
+            max_string_length: 8,
+        };
+
+        with_read_limits_sync(limits, ||{
+            let data = u32::MAX.to_le_bytes();
+
+            assert!(matches!(Vec::<()>::deserialize(&mut &data[..]), Err(Error::LimitExceeded { .. })));
+            assert!(matches!(HashMap::<u32, ()>::deserialize(&mut &data[..]), Err(Error::LimitExceeded { .. })));
+            assert!(matches!(String::deserialize(&mut &[9u8, 0][..]), Err(Error::LimitExceeded { .. })));
+            assert!(matches!(QBuffer::deserialize(&mut &[17u8, 0][..]), Err(Error::LimitExceeded { .. })));
+
+            let any = Any{ name: "a".to_owned(), data: vec![0; 17] }.to_data();
+            assert!(matches!(Any::deserialize(&mut &any[..]), Err(Error::LimitExceeded { .. })));
+
+            let message = [&17u32.to_le_bytes()[..], &[0x81], &[0; 8]].concat();
+            assert!(RMCMessage::new(&mut Cursor::new(message)).is_err());
+
+            // within the limits but not backed by any data
+            assert!(matches!(Vec::<u8>::deserialize(&mut &4u32.to_le_bytes()[..]), Err(Error::Io(_))));
+        });
+    }
+}
@@ -4,6 +4,7 @@ use std::mem::MaybeUninit;
 use bytemuck::bytes_of;
 use serde::Serialize;
 use crate::endianness::{IS_BIG_ENDIAN, ReadExtensions};
+use crate::rmc::structures::limits::{check_elements, MAX_PREALLOCATED_ELEMENTS};
 use crate::rmc::structures::RmcSerialize;
 
 
@@ -25,7 +26,9 @@ impl<T: RmcSerialize> RmcSerialize for Vec<T>{
     fn deserialize(mut reader: &mut dyn Read) -> crate::rmc::structures::Result<Self> {
         let len: u32 = reader.read_struct(IS_BIG_ENDIAN)?;
 
-        let mut vec = Vec::with_capacity(len as usize);
+        check_elements(len)?;
+
+        let mut vec = Vec::with_capacity((len as usize).min(MAX_PREALLOCATED_ELEMENTS));
 
         for _ in 0..len{
             vec.push(T::deserialize(reader)?);
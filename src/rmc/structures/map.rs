@@ -0,0 +1,76 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::io::{Read, Write};
+use bytemuck::bytes_of;
+use crate::endianness::{IS_BIG_ENDIAN, ReadExtensions};
+use crate::rmc::structures::limits::check_elements;
+use crate::rmc::structures::RmcSerialize;
+
+// a `Map<K, V>` is a u32 count followed by the key value pairs
+
+fn serialize_map<'a, K: RmcSerialize + 'a, V: RmcSerialize + 'a>(
+    writer: &mut dyn Write,
+    len: usize,
+    entries: impl Iterator<Item = (&'a K, &'a V)>
+) -> crate::rmc::structures::Result<()>{
+    let u32_len = len as u32;
+
+    writer.write_all(bytes_of(&u32_len))?;
+
+    for (k, v) in entries{
+        k.serialize(writer)?;
+        v.serialize(writer)?;
+    }
+
+    Ok(())
+}
+
+fn deserialize_map<K: RmcSerialize, V: RmcSerialize, M: FromIterator<(K, V)>>(mut reader: &mut dyn Read) -> crate::rmc::structures::Result<M>{
+    let len: u32 = reader.read_struct(IS_BIG_ENDIAN)?;
+
+    check_elements(len)?;
+
+    (0..len)
+        .map(|_| Ok((K::deserialize(reader)?, V::deserialize(reader)?)))
+        .collect()
+}
+
+impl<K: RmcSerialize + Eq + Hash, V: RmcSerialize> RmcSerialize for HashMap<K, V>{
+    fn serialize(&self, writer: &mut dyn Write) -> crate::rmc::structures::Result<()> {
+        serialize_map(writer, self.len(), self.iter())
+    }
+
+    fn deserialize(reader: &mut dyn Read) -> crate::rmc::structures::Result<Self> {
+        deserialize_map(reader)
+    }
+}
+
+/// Use this over a [`HashMap`] if the order of the entries matters.
+impl<K: RmcSerialize + Ord, V: RmcSerialize> RmcSerialize for BTreeMap<K, V>{
+    fn serialize(&self, writer: &mut dyn Write) -> crate::rmc::structures::Result<()> {
+        serialize_map(writer, self.len(), self.iter())
+    }
+
+    fn deserialize(reader: &mut dyn Read) -> crate::rmc::structures::Result<Self> {
+        deserialize_map(reader)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::collections::{BTreeMap, HashMap};
+    use crate::rmc::structures::RmcSerialize;
+
+    #[test]
+    fn maps(){
+        let map: BTreeMap<u32, String> = [(1, "a".to_owned()), (2, "b".to_owned())].into_iter().collect();
+
+        let data = map.to_data();
+
+        assert_eq!(data, [&2u32.to_le_bytes()[..], &1u32.to_le_bytes(), &[2, 0, b'a', 0], &2u32.to_le_bytes(), &[2, 0, b'b', 0]].concat());
+        assert_eq!(BTreeMap::<u32, String>::deserialize(&mut &data[..]).unwrap(), map);
+
+        let map: HashMap<String, Vec<u8>> = [("x".to_owned(), vec![1, 2])].into_iter().collect();
+        assert_eq!(HashMap::<String, Vec<u8>>::deserialize(&mut &map.to_data()[..]).unwrap(), map);
+    }
+}
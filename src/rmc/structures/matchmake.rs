@@ -93,10 +93,10 @@ pub struct CreateMatchmakeSessionParam {
     pub participation_count: u16,
 }
 
-#[derive(RmcSerialize, Debug, Clone)]
+#[derive(RmcSerialize, Debug, Clone, Default)]
 #[rmc_struct(0)]
 pub struct MatchmakeBlockListParam {
-    option_flag: u32,
+    pub option_flag: u32,
 }
 
 #[derive(RmcSerialize, Debug, Clone)]
@@ -111,8 +111,10 @@ pub struct JoinMatchmakeSessionParam {
     pub system_password: String,
     pub join_message: String,
     pub participation_count: u16,
-    //pub extra_participant: u16,
-    //pub block_list_param: MatchmakeBlockListParam
+    #[min_version(4, 0)]
+    pub extra_participant: u16,
+    #[min_version(4, 0)]
+    pub block_list_param: MatchmakeBlockListParam
 }
 
 pub mod gathering_flags {
@@ -124,3 +126,46 @@ pub mod gathering_flags {
     pub const VERBOSE_PARTICIPANTS: u32 = 0x400;
     pub const VERBOSE_PARTICIPANTS_EX: u32 = 0x800;
 }
+
+#[cfg(test)]
+mod test{
+    use crate::rmc::structures::matchmake::{JoinMatchmakeSessionParam, MatchmakeBlockListParam};
+    use crate::rmc::structures::RmcSerialize;
+    use crate::versions::{with_nex_version_sync, NexVersion};
+
+    #[test]
+    fn fields_depend_on_version(){
+        let param = JoinMatchmakeSessionParam{
+            gid: 1,
+            additional_participants: Vec::new(),
+            gid_for_participation_check: 0,
+            join_matchmake_session_open: 0,
+            join_matchmake_session_behavior: 0,
+            user_password: String::new(),
+            system_password: String::new(),
+            join_message: String::new(),
+            participation_count: 1,
+            extra_participant: 2,
+            block_list_param: MatchmakeBlockListParam{
+                option_flag: 3,
+            },
+        };
+
+        let old = with_nex_version_sync(NexVersion::new(3, 5, 0), || param.to_data());
+        let new = with_nex_version_sync(NexVersion::new(4, 0, 0), || param.to_data());
+
+        // the u16 and the block list param with its own structure header
+        assert_eq!(new.len(), old.len() + 2 + 5 + 4);
+
+        let read_old = with_nex_version_sync(NexVersion::new(3, 5, 0), || {
+            JoinMatchmakeSessionParam::deserialize(&mut &old[..])
+        }).unwrap();
+        assert_eq!(read_old.extra_participant, 0);
+
+        let read_new = with_nex_version_sync(NexVersion::new(4, 0, 0), || {
+            JoinMatchmakeSessionParam::deserialize(&mut &new[..])
+        }).unwrap();
+        assert_eq!(read_new.extra_participant, 2);
+        assert_eq!(read_new.block_list_param.option_flag, 3);
+    }
+}
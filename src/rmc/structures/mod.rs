@@ -2,6 +2,7 @@ use std::io;
 use std::io::{Read, Write};
 use std::string::FromUtf8Error;
 use thiserror::Error;
+use crate::kerberos::InvalidDateTime;
 
 //ideas for the future: make a proc macro library which allows generation of struct reads
 
@@ -16,7 +17,22 @@ pub enum Error{
     #[error("version mismatch: {0}")]
     VersionMismatch(u8),
     #[error("an error occurred reading the station url")]
-    StationUrlInvalid
+    StationUrlInvalid,
+    #[error("any data holder contains {found} instead of {expected}")]
+    AnyTypeMismatch{
+        expected: &'static str,
+        found: String,
+    },
+    #[error("any data holder lengths dont match: {0} and {1}")]
+    AnyLengthMismatch(u32, u32),
+    #[error("{0}")]
+    InvalidDateTime(#[from] InvalidDateTime),
+    #[error("{what} of {size} exceeds the limit of {max}")]
+    LimitExceeded{
+        what: &'static str,
+        size: u64,
+        max: u64,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -33,6 +49,12 @@ pub mod primitives;
 pub mod matchmake;
 pub mod variant;
 pub mod ranking;
+pub mod data;
+pub mod authentication_info;
+pub mod map;
+pub mod result_range;
+pub mod date_time;
+pub mod limits;
 mod networking;
 
 pub trait RmcSerialize{
@@ -1,6 +1,7 @@
 use std::io::{Read, Write};
 use bytemuck::bytes_of;
 use crate::endianness::{IS_BIG_ENDIAN, ReadExtensions};
+use crate::rmc::structures::limits::check_size;
 use crate::rmc::structures::{Result, RmcSerialize};
 
 
@@ -20,6 +21,8 @@ impl RmcSerialize for QBuffer{
     fn deserialize(mut reader: &mut dyn Read) -> Result<Self> {
         let size: u16 = reader.read_struct(IS_BIG_ENDIAN)?;
 
+        check_size("qbuffer size", size as usize)?;
+
         let mut vec = vec![0; size as usize];
 
         reader.read_exact(&mut vec)?;
@@ -0,0 +1,42 @@
+use macros::RmcSerialize;
+
+/// Which part of a result list a client wants, e.g. for browsing gatherings or rankings.
+#[derive(RmcSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[rmc_struct(0)]
+pub struct ResultRange{
+    pub offset: u32,
+    pub length: u32,
+}
+
+impl ResultRange{
+    /// Gets the part of `items` this range covers.
+    pub fn apply<T>(&self, items: &[T]) -> &[T]{
+        let start = (self.offset as usize).min(items.len());
+        let end = start.saturating_add(self.length as usize).min(items.len());
+
+        &items[start..end]
+    }
+}
+
+impl Default for ResultRange{
+    fn default() -> Self {
+        Self{
+            offset: 0,
+            length: 10,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::rmc::structures::result_range::ResultRange;
+    use crate::rmc::structures::RmcSerialize;
+
+    #[test]
+    fn result_range(){
+        let range = ResultRange{ offset: 1, length: 2 };
+        assert_eq!(ResultRange::deserialize(&mut &range.to_data()[..]).unwrap(), range);
+        assert_eq!(range.apply(&[1, 2, 3, 4]), &[2, 3]);
+        assert!(ResultRange{ offset: 10, length: 2 }.apply(&[1, 2]).is_empty());
+    }
+}
@@ -1,8 +1,11 @@
 use std::io::{Cursor, Read, Write};
 use bytemuck::bytes_of;
+use log::debug;
 use crate::endianness::{IS_BIG_ENDIAN, ReadExtensions};
 use crate::rmc::structures::Error::VersionMismatch;
+use crate::rmc::structures::limits::check_size;
 use crate::rmc::structures::Result;
+use crate::versions::current_nex_version;
 
 #[repr(C, packed)]
 struct StructureHeader{
@@ -10,35 +13,105 @@ struct StructureHeader{
     length: u32
 }
 
-pub fn write_struct(writer: &mut dyn Write, version: u8, pred: impl FnOnce(&mut Vec<u8>) -> Result<()> ) -> Result<()> {
-    writer.write_all(&[version])?;
+/// Whether structures get a version and length header, this depends on the nex version of the
+/// connection being handled (or the game if that isnt known) as they only exist from nex 3.5
+/// onwards.
+pub fn structure_headers_enabled() -> bool{
+    current_nex_version().has_structure_headers()
+}
 
+pub fn write_struct(writer: &mut dyn Write, version: u8, pred: impl FnOnce(&mut Vec<u8>) -> Result<()> ) -> Result<()> {
     let mut scratch_space: Vec<u8> = Vec::new();
 
     (pred)(&mut scratch_space)?;
 
-    let u32_size = scratch_space.len() as u32;
+    if structure_headers_enabled(){
+        let u32_size = scratch_space.len() as u32;
+
+        writer.write_all(&[version])?;
+        writer.write_all(bytes_of(&u32_size))?;
+    }
 
-    writer.write_all(bytes_of(&u32_size))?;
     writer.write_all(&scratch_space)?;
 
     Ok(())
 }
 
-pub fn read_struct<T: Sized>(mut reader: &mut dyn Read, version: u8, pred: impl FnOnce(&mut Cursor<Vec<u8>>) -> Result<T>) -> Result<T> {
+pub fn read_struct<T: Sized>(mut reader: &mut dyn Read, version: u8, pred: impl FnOnce(&mut dyn Read) -> Result<T>) -> Result<T> {
+    if !structure_headers_enabled(){
+        return pred(reader);
+    }
+
     let ver: u8 = reader.read_struct(IS_BIG_ENDIAN)?;
 
-    if ver != version{
+    // newer versions only ever add fields at the end so we can still read them, older ones are
+    // missing fields though
+    if ver < version{
         return Err(VersionMismatch(ver));
     }
 
     let size: u32 = reader.read_struct(IS_BIG_ENDIAN)?;
 
+    check_size("structure size", size as usize)?;
+
     let mut vec = vec![0u8; size as usize];
 
     reader.read_exact(&mut vec)?;
 
     let mut cursor = Cursor::new(vec);
 
-    Ok(pred(&mut cursor)?)
-}
\ No newline at end of file
+    let out = pred(&mut cursor)?;
+
+    let unread = cursor.get_ref().len() as u64 - cursor.position();
+
+    if unread != 0{
+        debug!("skipped {} unknown bytes of structure version {} (expected {})", unread, ver, version);
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test{
+    use crate::rmc::structures::rmc_struct::{read_struct, write_struct};
+    use crate::rmc::structures::RmcSerialize;
+    use crate::versions::{with_nex_version_sync, NexVersion};
+
+    fn write(version: u8, fields: &[u32]) -> Vec<u8>{
+        let mut data = Vec::new();
+
+        write_struct(&mut data, version, |writer| {
+            for field in fields {
+                field.serialize(writer)?;
+            }
+            Ok(())
+        }).unwrap();
+
+        data
+    }
+
+    fn read(version: u8, data: &[u8]) -> crate::rmc::structures::Result<u32>{
+        read_struct(&mut &data[..], version, |reader| u32::deserialize(reader))
+    }
+
+    #[test]
+    fn newer_versions_get_skipped(){
+        with_nex_version_sync(NexVersion::new(3, 5, 0), || {
+            let newer = write(2, &[1, 2]);
+
+            assert_eq!(newer.len(), 5 + 8);
+            assert_eq!(read(1, &newer).unwrap(), 1);
+            assert!(read(3, &newer).is_err());
+        });
+    }
+
+    #[test]
+    fn no_headers_before_3_5(){
+        with_nex_version_sync(NexVersion::new(3, 4, 0), || {
+            let data = write(1, &[1]);
+
+            assert_eq!(data, 1u32.to_le_bytes());
+            assert_eq!(read(1, &data).unwrap(), 1);
+        });
+    }
+}
@@ -2,16 +2,25 @@ use std::io::{Read, Write};
 use bytemuck::bytes_of;
 use log::error;
 use crate::endianness::{IS_BIG_ENDIAN, ReadExtensions};
+use super::limits::check_string_length;
 use super::{Result, RmcSerialize};
 
 impl RmcSerialize for String{
     fn deserialize(mut reader: &mut dyn Read) -> Result<Self> {
         let len: u16 = reader.read_struct(IS_BIG_ENDIAN)?;
-        let mut data = vec![0; len as usize - 1];
+
+        // a length of 0 doesnt even leave room for the null terminator, treat it as empty
+        if len == 0{
+            return Ok(String::new());
+        }
+
+        check_string_length(len)?;
+
+        let mut data = vec![0; len as usize];
         reader.read_exact(&mut data)?;
 
-        let null: u8 = reader.read_struct(IS_BIG_ENDIAN)?;
-        if null != 0{
+        let null = data.pop();
+        if null != Some(0){
             error!("unable to find null terminator... continuing anyways");
         }
 
@@ -0,0 +1,85 @@
+//! Optional logging of the decoded parameters and return values of every rmc call which gets
+//! handled, this gets logged on the debug level so it ends up in the log file.
+//!
+//! Values get printed with their [`Debug`] implementation, types which dont have one just show
+//! up as their type name. The auth and secure protocols never get traced as their calls carry
+//! tokens, tickets and keys.
+
+use std::any::type_name;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::rmc::protocols::auth::RawAuthInfo;
+use crate::rmc::protocols::secure::RawSecureInfo;
+
+static TRACING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn tracing_enabled() -> bool{
+    TRACING_ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_tracing_enabled(enabled: bool){
+    TRACING_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Protocols whose parameters and return values would leak credentials into the logs.
+const UNTRACED_PROTOCOLS: &[u16] = &[RawAuthInfo::PROTOCOL_ID, RawSecureInfo::PROTOCOL_ID];
+
+/// Whether calls of this protocol should get traced.
+pub fn tracing_enabled_for(protocol_id: u16) -> bool{
+    tracing_enabled() && !UNTRACED_PROTOCOLS.contains(&protocol_id)
+}
+
+// the generated code calls `(&TraceValue(&value)).trace_fmt()`, method resolution picks
+// `TraceDebug` if the value is `Debug` and only falls back to `TraceFallback` (which needs one
+// more reference) if it isnt
+
+#[doc(hidden)]
+pub struct TraceValue<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait TraceDebug{
+    fn trace_fmt(&self) -> String;
+}
+
+impl<T: Debug + ?Sized> TraceDebug for TraceValue<'_, T>{
+    fn trace_fmt(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait TraceFallback{
+    fn trace_fmt(&self) -> String;
+}
+
+impl<T: ?Sized> TraceFallback for &TraceValue<'_, T>{
+    fn trace_fmt(&self) -> String {
+        format!("<{}>", type_name::<T>())
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::rmc::trace::{set_tracing_enabled, tracing_enabled_for, TraceDebug, TraceFallback, TraceValue};
+
+    struct NoDebug;
+
+    #[test]
+    fn falls_back_to_type_name(){
+        assert_eq!((&TraceValue(&vec![1u32, 2])).trace_fmt(), "[1, 2]");
+        assert!((&TraceValue(&NoDebug)).trace_fmt().ends_with("NoDebug>"));
+    }
+
+    #[test]
+    fn credentials_dont_get_traced(){
+        set_tracing_enabled(true);
+
+        assert!(tracing_enabled_for(109));
+        assert!(!tracing_enabled_for(10));
+        assert!(!tracing_enabled_for(11));
+
+        set_tracing_enabled(false);
+
+        assert!(!tracing_enabled_for(109));
+    }
+}
@@ -1,11 +1,29 @@
 use macros::RmcSerialize;
 use crate::prudp::sockaddr::PRUDPSockAddr;
+use crate::versions::NexVersion;
 
 #[derive(Debug, RmcSerialize)]
 #[rmc_struct(0)]
 pub struct ConnectionInitData{
     pub prudpsock_addr: PRUDPSockAddr,
     pub pid: u32,
-    
+    /// packed nex version of the client, 0 if it isnt known
+    pub nex_version: u32,
 }
 
+impl ConnectionInitData{
+    pub fn new(prudpsock_addr: PRUDPSockAddr, pid: u32, nex_version: Option<NexVersion>) -> Self{
+        Self{
+            prudpsock_addr,
+            pid,
+            nex_version: nex_version.map(|v| v.to_packed()).unwrap_or(0),
+        }
+    }
+
+    pub fn nex_version(&self) -> Option<NexVersion>{
+        match self.nex_version{
+            0 => None,
+            v => Some(NexVersion::from_packed(v)),
+        }
+    }
+}
@@ -1,10 +1,15 @@
+use std::io;
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use log::{error, info};
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tokio::sync::Notify;
 use tokio::task;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
 use rust_nex::reggie::{UnitPacketRead, UnitPacketWrite};
 
 #[derive(Clone)]
@@ -87,6 +92,14 @@ impl SplittableBufferConnection {
 
         Self(SendingBufferConnection(outside_send, notify), outside_recv)
     }
+
+    /// Creates two connections which are connected to each other in memory, this is mostly
+    /// useful for testing rmc objects without sockets.
+    pub fn pair() -> (Self, Self){
+        let (a, b) = tokio::io::duplex(64 * 1024);
+
+        (Self::new(a), Self::new(b))
+    }
 }
 
 impl SendingBufferConnection{
@@ -113,3 +126,38 @@ impl SplittableBufferConnection{
         self.0.clone()
     }
 }
+
+/// Polls the modification time of `path` and calls `reload` on a blocking thread whenever it
+/// changed (and once at the start), this is how config files get picked up without a restart.
+pub fn watch_file<F>(path: PathBuf, interval: Duration, reload: F) -> JoinHandle<()>
+    where F: Fn(&Path) -> io::Result<()> + Send + Sync + 'static
+{
+    let reload = Arc::new(reload);
+
+    tokio::spawn(async move {
+        let mut last_modified: Option<SystemTime> = None;
+
+        loop {
+            let modified = tokio::fs::metadata(&path).await.and_then(|m| m.modified());
+
+            match modified{
+                Ok(modified) if Some(modified) != last_modified => {
+                    let reload = reload.clone();
+                    let reload_path = path.clone();
+
+                    match task::spawn_blocking(move || reload(&reload_path)).await{
+                        Ok(Ok(())) => info!("reloaded {}", path.display()),
+                        Ok(Err(e)) => error!("unable to reload {}: {}", path.display(), e),
+                        Err(e) => error!("reloading {} panicked: {}", path.display(), e)
+                    }
+
+                    last_modified = Some(modified);
+                }
+                Ok(_) => {}
+                Err(e) => error!("unable to read {}: {}", path.display(), e)
+            }
+
+            sleep(interval).await;
+        }
+    })
+}
@@ -1,97 +1,66 @@
-use std::marker::PhantomData;
-use std::ops::{BitAnd, BitOr};
-use typenum::{Cmp, IsEqual, IsLess, IsLessOrEqual, Unsigned};
-
-/// This trait represents a version at compile time
-trait Version{
-    type Major: Unsigned;
-    type Minor: Unsigned;
+use std::sync::atomic::{AtomicU32, Ordering};
+use crate::rmc::connection_context::ConnectionContext;
+
+/// A nex version, the version of a connection is only known once the client tells us which nex
+/// version it is running.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct NexVersion{
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
 }
 
-/// This struct contains nothing and is used to represent specific versions as an instance of
-/// [`Version`]. It is instances as `Ver<Major, Minor>`
-struct Ver<MAJ: Unsigned, MIN: Unsigned>{
-    _phantom: PhantomData<(MAJ, MIN)>
+impl NexVersion{
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self{
+        Self{
+            major,
+            minor,
+            patch
+        }
+    }
+
+    /// Decodes the packed form clients send us (e.g. `30502` for 3.5.2).
+    pub const fn from_packed(version: u32) -> Self{
+        Self{
+            major: version / 10000,
+            minor: (version / 100) % 100,
+            patch: version % 100
+        }
+    }
+
+    /// Encodes the version the same way clients send it (e.g. 3.5.2 becomes `30502`).
+    pub const fn to_packed(&self) -> u32{
+        self.major * 10000 + self.minor * 100 + self.patch
+    }
+
+    /// Structures only have a version and length header from nex 3.5 onwards.
+    pub fn has_structure_headers(&self) -> bool{
+        *self >= Self::new(3, 5, 0)
+    }
 }
 
-impl<MAJ: Unsigned, MIN: Unsigned> Version for Ver<MAJ, MIN>{
-    type Major = MAJ;
-    type Minor = MIN;
-}
+// the nex version the game runs on, this gets used whenever the version of a connection isnt known
+static DEFAULT_NEX_VERSION: AtomicU32 = AtomicU32::new(NexVersion::new(3, 5, 0).to_packed());
 
-/// Represents two versions which can be compared
-trait ComparableVersion<T: Version>: Version{
-    type IsAtLeast: SameOrUnit;
+pub fn default_nex_version() -> NexVersion{
+    NexVersion::from_packed(DEFAULT_NEX_VERSION.load(Ordering::Relaxed))
 }
 
-impl<T: Version, U: Version> ComparableVersion<T> for U where
-    <T as Version>::Major: Cmp<Self::Major>,
-    <T as Version>::Minor: IsLessOrEqual<Self::Minor>,
-    <T as Version>::Major: IsEqual<
-        Self::Major,
-        Output: BitAnd<
-            typenum::LeEq<T::Minor, Self::Minor>
-        >,
-    >,
-    <T as Version>::Major: IsLess<
-        Self::Major,
-        Output: BitOr<
-            typenum::And<
-                typenum::Eq<T::Major, Self::Major>,
-                typenum::LeEq<T::Minor, Self::Minor>,
-            >,
-            Output: SameOrUnit
-        >
-    > {
-
-    type IsAtLeast = typenum::Or<
-        typenum::Le<T::Major, Self::Major>,
-        typenum::And<
-            typenum::Eq<T::Major, Self::Major>,
-            typenum::LeEq<T::Minor, Self::Minor>,
-        >
-    >;
+pub fn set_default_nex_version(version: NexVersion){
+    DEFAULT_NEX_VERSION.store(version.to_packed(), Ordering::Relaxed);
 }
 
-
-/// Simple check for testing if the `TEST` version is at least `REQ` or higher.
-type VersionAbove<REQ, TEST> = <TEST as ComparableVersion<REQ>>::IsAtLeast;
-
-trait VersionIsAtLeast<VER: Version>{}
-
-impl<VER: Version, T: ComparableVersion<VER, IsAtLeast = typenum::True>> VersionIsAtLeast<VER> for T{}
-
-
-/// Trait for containing the result of elements which only conditionally exist
-trait CondElemResult{
-    type Output;
+/// Gets the nex version (de)serialization should follow, this is the version of the connection
+/// which is currently being handled or the default version otherwise.
+pub fn current_nex_version() -> NexVersion{
+    ConnectionContext::current().nex_version.unwrap_or_else(default_nex_version)
 }
 
-/// Empty helper struct which only servers to give a concrete type when creating fields in rmc
-/// structs which have a version requirement. This is not meant to be used directly, use
-/// [`MinVersion`] instead.
-struct MinVersionElementHelper<T, REQUIRED: Version, VER: Version + ComparableVersion<REQUIRED>>{
-    _phantom: PhantomData<(T, REQUIRED, VER)>
+/// Runs `f` with `version` as the [`current_nex_version`], the rest of the
+/// [`ConnectionContext`] stays the same.
+pub fn with_nex_version_sync<R>(version: NexVersion, f: impl FnOnce() -> R) -> R{
+    ConnectionContext{
+        nex_version: Some(version),
+        ..ConnectionContext::current()
+    }.sync_scope(f)
 }
-
-/// This should be used either with [`typenum::True`] or [`typenum::False`]. When `True` the [`Self::Output`]
-/// will be the same as the `T` you put into Output. When `False` it will always be `()`
-trait SameOrUnit{
-    type Output<T>;
-}
-
-impl SameOrUnit for typenum::True{
-    type Output<T> = T;
-}
-
-impl SameOrUnit for typenum::False{
-    type Output<T> = ();
-}
-
-impl<T, REQUIRED: Version, VER: Version + ComparableVersion<REQUIRED>> CondElemResult for MinVersionElementHelper<T, REQUIRED, VER> where {
-    type Output = <<VER as ComparableVersion<REQUIRED>>::IsAtLeast as SameOrUnit>::Output<T>;
-}
-
-/// When the version condition is met the field will exist and will simply be `T` if not it will be
-/// replaced by `()`. Use this when you need to add versioning to rmc structs.
-type MinVersion<T, REQUIRED, VER> = <MinVersionElementHelper<T, REQUIRED, VER> as CondElemResult>::Output;
\ No newline at end of file
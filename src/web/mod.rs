@@ -1,11 +1,18 @@
 use std::sync::Arc;
 use async_trait::async_trait;
 use rocket::{get, routes, Request, State};
+use rocket::http::Status;
 use rocket::request::{FromRequest, Outcome};
 use rocket::serde::json::Json;
+use serde::Serialize;
 use tokio::task::JoinHandle;
+use crate::kerberos::KerberosDateTime;
+use crate::nex::account_lookup::ACCOUNT_LOOKUP_CACHE;
+use crate::nex::account_provider::AccountBackend;
 use crate::nex::matchmake::MatchmakeManager;
 use crate::rmc::protocols::notifications::NotificationEvent;
+use crate::rmc::protocols::unimplemented_call_counts;
+use crate::rmc::response::ErrorCode;
 
 struct RnexApiAuth;
 
@@ -26,6 +33,30 @@ async fn gatherings(mmm: &State<Arc<MatchmakeManager>>) -> Json<Vec<u32>>{
     Json(matches.keys().map(|v| *v).collect())
 }
 
+#[derive(Serialize)]
+struct GatheringInfo{
+    gid: u32,
+    owner_pid: u32,
+    host_pid: u32,
+    participation_count: u32,
+    created_at: KerberosDateTime,
+}
+
+#[get("/gathering/<gid>")]
+async fn gathering_info(mmm: &State<Arc<MatchmakeManager>>, gid: u32) -> Option<Json<GatheringInfo>>{
+    let gathering = mmm.get_session(gid).await.ok()?;
+
+    let gathering = gathering.lock().await;
+
+    Some(Json(GatheringInfo{
+        gid: gathering.session.gathering.self_gid,
+        owner_pid: gathering.session.gathering.owner_pid,
+        host_pid: gathering.session.gathering.host_pid,
+        participation_count: gathering.session.participation_count,
+        created_at: gathering.session.datetime,
+    }))
+}
+
 #[get("/gathering/<gid>/players")]
 async fn players_in_match(mmm: &State<Arc<MatchmakeManager>>, gid: u32) -> Option<Json<Vec<u32>>>{
     let mmm = mmm.sessions.read().await;
@@ -56,6 +87,40 @@ async fn disconnect_player(_auth: RnexApiAuth, mmm: &State<Arc<MatchmakeManager>
     Some(())
 }*/
 
+/// Only a missing account is a 404, everything else means the account backend is having issues.
+fn lookup_status(error: ErrorCode) -> Status{
+    match error{
+        ErrorCode::RendezVous_InvalidPID | ErrorCode::RendezVous_InvalidUsername => Status::NotFound,
+        _ => Status::BadGateway,
+    }
+}
+
+#[get("/user/<pid>/name")]
+async fn user_name(_auth: RnexApiAuth, accounts: &State<Arc<AccountBackend>>, pid: u32) -> Result<Json<String>, Status>{
+    ACCOUNT_LOOKUP_CACHE.get_name(accounts.as_ref(), pid).await.map(Json).map_err(lookup_status)
+}
+
+#[get("/user/by-name/<username>/pid")]
+async fn user_pid(_auth: RnexApiAuth, accounts: &State<Arc<AccountBackend>>, username: &str) -> Result<Json<u32>, Status>{
+    ACCOUNT_LOOKUP_CACHE.get_pid(accounts.as_ref(), username).await.map(Json).map_err(lookup_status)
+}
+
+#[derive(Serialize)]
+struct UnimplementedCall{
+    protocol_id: u16,
+    method_id: u32,
+    count: u64,
+}
+
+#[get("/stats/unimplemented")]
+async fn unimplemented_calls(_auth: RnexApiAuth) -> Json<Vec<UnimplementedCall>>{
+    Json(unimplemented_call_counts().into_iter().map(|(protocol_id, method_id, count)| UnimplementedCall{
+        protocol_id,
+        method_id,
+        count,
+    }).collect())
+}
+
 #[get("/gathering/<gid>/close")]
 async fn close_gathering(_auth: RnexApiAuth, mmm: &State<Arc<MatchmakeManager>>, gid: u32) -> Option<()>{
     // this doesnt work and is broken, there might be some other way to remotely close gatherings...
@@ -81,11 +146,12 @@ async fn close_gathering(_auth: RnexApiAuth, mmm: &State<Arc<MatchmakeManager>>,
     Some(())
 }
 
-pub async fn start_web(mgr: Arc<MatchmakeManager>) -> JoinHandle<()> {
+pub async fn start_web(mgr: Arc<MatchmakeManager>, accounts: Arc<AccountBackend>) -> JoinHandle<()> {
     tokio::spawn(async move {
         rocket::build()
-            .mount("/", routes![gatherings, players_in_match, close_gathering])
+            .mount("/", routes![gatherings, gathering_info, players_in_match, close_gathering, user_name, user_pid, unimplemented_calls])
             .manage(mgr)
+            .manage(accounts)
             .launch().await
             .expect("unable to start webserver");
     })